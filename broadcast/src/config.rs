@@ -0,0 +1,139 @@
+use std::time::Duration;
+
+use maelstrom::{
+    config::{NodeConfig, Settings},
+    encoding::Encoding,
+    error::MaelstromError,
+    topology::Topology,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrategyKind {
+    NaiveFlood,
+    ReliableFlood,
+    AckFlood,
+    FullState,
+    Delta,
+    ProbabilisticResend,
+    Plumtree,
+    PushPull,
+    Merkle,
+}
+
+impl StrategyKind {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "naive-flood" => Some(Self::NaiveFlood),
+            "reliable-flood" => Some(Self::ReliableFlood),
+            "ack-flood" => Some(Self::AckFlood),
+            "full-state" => Some(Self::FullState),
+            "delta" => Some(Self::Delta),
+            "probabilistic-resend" => Some(Self::ProbabilisticResend),
+            "plumtree" => Some(Self::Plumtree),
+            "push-pull" => Some(Self::PushPull),
+            "merkle" => Some(Self::Merkle),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub strategy: StrategyKind,
+    // How often periodic strategies gossip and ack-based ones and reliable
+    // links retry.
+    pub interval: Duration,
+    // The share of values a peer already has that probabilistic resend sends
+    // again anyway.
+    pub resend_ratio: f64,
+    // How many peers push-pull and merkle reconcile with every interval.
+    pub fanout: usize,
+    pub topology: Topology,
+    // The encodings of value sets this node reads, most preferred first.
+    pub encodings: Vec<Encoding>,
+    // How long gossip to a peer is held back to be coalesced with more, zero
+    // to send it straight away, and how many payloads a batch takes at most.
+    pub batch_window: Duration,
+    pub batch_size: usize,
+    // How often plumtree announces values to its lazy peers, and how long it
+    // waits for an announced value before grafting it.
+    pub lazy_interval: Duration,
+    pub graft_timeout: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            strategy: StrategyKind::Delta,
+            interval: Duration::from_millis(200),
+            resend_ratio: 0.1,
+            fanout: 1,
+            topology: Topology::Full,
+            encodings: vec![Encoding::Intervals, Encoding::Deltas, Encoding::Plain],
+            batch_window: Duration::ZERO,
+            batch_size: 64,
+            lazy_interval: Duration::from_secs(1),
+            graft_timeout: Duration::from_millis(500),
+        }
+    }
+}
+
+// Settings are read from `--strategy`, `--interval-ms`, `--resend-ratio`,
+// `--fanout`, `--topology`, `--encodings`, `--batch-ms`, `--batch-size`,
+// `--lazy-interval-ms` and `--graft-timeout-ms`, or from `BROADCAST_STRATEGY`
+// and so on. Maelstrom runs the binary without arguments, so challenge
+// profiles use the environment.
+impl NodeConfig for Config {
+    const PREFIX: &'static str = "BROADCAST";
+
+    fn load(settings: &mut Settings) -> Result<Self, MaelstromError> {
+        let default = Self::default();
+        Ok(Self {
+            strategy: settings.parse(
+                "strategy",
+                default.strategy,
+                "naive-flood, reliable-flood, ack-flood, full-state, delta, probabilistic-resend, plumtree, push-pull or merkle",
+                StrategyKind::parse,
+            )?,
+            interval: settings.millis(
+                "interval-ms",
+                default.interval,
+                Duration::from_millis(1),
+                Duration::from_secs(60),
+            )?,
+            resend_ratio: settings.number("resend-ratio", default.resend_ratio, 0.0, 1.0)?,
+            fanout: settings.number("fanout", default.fanout, 1, 1024)?,
+            topology: settings.parse(
+                "topology",
+                default.topology,
+                "full, maelstrom, spanning-tree, tree:K, grid, ring-with-chords or random:K",
+                Topology::parse,
+            )?,
+            encodings: settings.parse(
+                "encodings",
+                default.encodings,
+                "a comma-separated list of plain, intervals and deltas",
+                |value| value.split(',').map(Encoding::parse).collect(),
+            )?,
+            batch_window: settings.millis(
+                "batch-ms",
+                default.batch_window,
+                Duration::ZERO,
+                Duration::from_secs(60),
+            )?,
+            batch_size: settings.number("batch-size", default.batch_size, 1, 65536)?,
+            lazy_interval: settings.millis(
+                "lazy-interval-ms",
+                default.lazy_interval,
+                Duration::from_millis(1),
+                Duration::from_secs(60),
+            )?,
+            graft_timeout: settings.millis(
+                "graft-timeout-ms",
+                default.graft_timeout,
+                Duration::from_millis(1),
+                Duration::from_secs(60),
+            )?,
+        })
+    }
+}
@@ -0,0 +1,146 @@
+mod config;
+mod strategy;
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use maelstrom::{
+    error::MaelstromError,
+    message::{InitializationRequest, Message},
+    node::MaelstromNode,
+    service::Service,
+};
+
+use config::Config;
+use strategy::{GossipStrategy, PeerPayload};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum BroadcastRequest {
+    Broadcast {
+        message: usize,
+    },
+    Read,
+    Topology {
+        topology: HashMap<String, Vec<String>>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(clippy::enum_variant_names)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum BroadcastResponse {
+    BroadcastOk,
+    ReadOk { messages: HashSet<usize> },
+    TopologyOk,
+}
+
+// Every broadcast challenge runs this node; the gossip strategy, its
+// parameters and the topology come from the configuration.
+struct BroadcastNode {
+    id: String,
+    node_ids: Vec<String>,
+    config: Config,
+    strategy: Box<dyn GossipStrategy>,
+}
+
+impl MaelstromNode for BroadcastNode {
+    type InputPayload = BroadcastRequest;
+    type OutputPayload = BroadcastResponse;
+    type PeerPayload = PeerPayload;
+    type Config = Config;
+
+    fn new(init_message: &Message<InitializationRequest>, config: Self::Config) -> Self {
+        let InitializationRequest::Init { id, neighbors } = init_message.payload();
+        let peers = match config.topology.is_suggested() {
+            true => vec![],
+            false => config.topology.neighbors(id, neighbors, &HashMap::new()),
+        };
+
+        Self {
+            id: id.clone(),
+            node_ids: neighbors.clone(),
+            strategy: strategy::build(&config, id, peers),
+            config,
+        }
+    }
+
+    fn handle(
+        &mut self,
+        message: &Message<Self::InputPayload>,
+        service: &mut Service,
+    ) -> Result<Option<Self::OutputPayload>, MaelstromError>
+    where
+        Self: Sized,
+    {
+        match message.payload() {
+            BroadcastRequest::Broadcast { message: value } => {
+                self.strategy.broadcast(service, *value)?;
+                Ok(Some(BroadcastResponse::BroadcastOk))
+            }
+            BroadcastRequest::Read => Ok(Some(BroadcastResponse::ReadOk {
+                messages: self.strategy.values(),
+            })),
+            BroadcastRequest::Topology { topology } => {
+                if self.config.topology.is_suggested() {
+                    let peers = self
+                        .config
+                        .topology
+                        .neighbors(&self.id, &self.node_ids, topology);
+                    self.strategy.set_peers(peers);
+                }
+
+                Ok(Some(BroadcastResponse::TopologyOk))
+            }
+        }
+    }
+
+    fn start(&mut self, service: &mut Service) -> Result<(), MaelstromError> {
+        service.every(self.config.interval);
+        service.retransmit_every(self.config.interval);
+        if !self.config.batch_window.is_zero() {
+            service.batch(self.config.batch_window, self.config.batch_size)?;
+        }
+
+        // Each pair of nodes only needs one side to say hello.
+        let later = self
+            .node_ids
+            .iter()
+            .filter(|&node| node > &self.id)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        self.strategy.links().hello(service, &later)
+    }
+
+    fn handle_timer(&mut self, _: usize, service: &mut Service) -> Result<(), MaelstromError> {
+        self.strategy.tick(service)
+    }
+
+    fn handle_peer(
+        &mut self,
+        message: &Message<Self::PeerPayload>,
+        service: &mut Service,
+    ) -> Result<Option<Self::PeerPayload>, MaelstromError> {
+        let links = self.strategy.links();
+        match message.payload() {
+            PeerPayload::Hello { encodings } => {
+                links.accept(message.src(), encodings);
+                Ok(Some(PeerPayload::HelloOk {
+                    encodings: links.encodings().to_vec(),
+                }))
+            }
+            PeerPayload::HelloOk { encodings } => {
+                links.accept(message.src(), encodings);
+                Ok(None)
+            }
+            _ => self.strategy.receive(service, message),
+        }
+    }
+}
+
+pub fn main() -> anyhow::Result<()> {
+    Service::new().run::<BroadcastNode>()?;
+    Ok(())
+}
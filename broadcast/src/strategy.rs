@@ -0,0 +1,897 @@
+use std::collections::{HashMap, HashSet};
+
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use maelstrom::{
+    anti_entropy::{AntiEntropy, Digest, Span},
+    batch::Coalesce,
+    crdt::GSet,
+    encoding::{Encoded, Encoding},
+    error::MaelstromError,
+    gossip::DeltaGossip,
+    merkle::{MerkleTree, Node, Step},
+    message::Message,
+    plumtree::{Outgoing, Plumtree},
+    service::Service,
+    topology::Topology,
+};
+
+use crate::config::{Config, StrategyKind};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PeerPayload {
+    Hello {
+        encodings: Vec<Encoding>,
+    },
+    HelloOk {
+        encodings: Vec<Encoding>,
+    },
+    Gossip {
+        messages: Encoded<HashSet<usize>>,
+    },
+    GossipOk {
+        messages: Encoded<HashSet<usize>>,
+    },
+    Delta {
+        seq: u64,
+        messages: Encoded<GSet<usize>>,
+    },
+    DeltaOk {
+        seq: u64,
+    },
+    Push {
+        root: String,
+        messages: Encoded<Vec<usize>>,
+    },
+    IHave {
+        messages: Vec<(String, usize)>,
+    },
+    IHaveOk {
+        messages: Encoded<Vec<usize>>,
+    },
+    Graft {
+        root: String,
+        messages: Encoded<Vec<usize>>,
+    },
+    GraftOk {
+        root: String,
+        messages: Encoded<Vec<usize>>,
+    },
+    Prune {
+        root: String,
+    },
+    Digest {
+        digest: Digest<usize>,
+    },
+    Reconcile {
+        spans: Vec<Span<usize>>,
+        messages: Encoded<Vec<usize>>,
+    },
+    ReconcileOk {
+        messages: Encoded<Vec<usize>>,
+    },
+    Hashes {
+        hashes: Vec<(Node, u64)>,
+    },
+    Buckets {
+        buckets: Vec<Node>,
+        messages: Encoded<Vec<usize>>,
+    },
+    BucketsOk {
+        messages: Encoded<Vec<usize>>,
+    },
+}
+
+// Batched gossip to a peer goes out as one set of values, in the encoding of
+// the first payload.
+impl Coalesce for PeerPayload {
+    fn coalesce(&mut self, next: Self) -> Option<Self> {
+        match (self, next) {
+            (Self::Gossip { messages }, Self::Gossip { messages: next })
+            | (Self::GossipOk { messages }, Self::GossipOk { messages: next }) => {
+                messages.values.extend(next.values);
+                None
+            }
+            (_, next) => Some(next),
+        }
+    }
+}
+
+// How a node spreads broadcast values to its peers. Strategies that relay
+// pass values on to peers other than the one they came from, so they work on
+// any connected topology; the rest only reach every node on a full mesh.
+pub trait GossipStrategy {
+    fn links(&mut self) -> &mut Links;
+
+    fn values(&self) -> HashSet<usize>;
+
+    fn set_peers(&mut self, peers: Vec<String>);
+
+    fn broadcast(&mut self, service: &mut Service, value: usize) -> Result<(), MaelstromError>;
+
+    fn tick(&mut self, _: &mut Service) -> Result<(), MaelstromError> {
+        Ok(())
+    }
+
+    fn receive(
+        &mut self,
+        service: &mut Service,
+        message: &Message<PeerPayload>,
+    ) -> Result<Option<PeerPayload>, MaelstromError>;
+}
+
+pub fn build(config: &Config, id: &str, peers: Vec<String>) -> Box<dyn GossipStrategy> {
+    let links = Links {
+        id: id.to_string(),
+        peers,
+        encodings: config.encodings.clone(),
+        negotiated: HashMap::new(),
+    };
+
+    match config.strategy {
+        StrategyKind::NaiveFlood => Box::new(NaiveFlood {
+            links,
+            values: HashSet::new(),
+        }),
+        StrategyKind::ReliableFlood => Box::new(ReliableFlood {
+            links,
+            values: HashSet::new(),
+        }),
+        StrategyKind::AckFlood => Box::new(AckFlood {
+            links,
+            values: HashSet::new(),
+            unacked: HashMap::new(),
+        }),
+        StrategyKind::FullState => Box::new(FullState {
+            links,
+            values: HashSet::new(),
+        }),
+        StrategyKind::Delta => Box::new(Delta {
+            gossip: DeltaGossip::new(&links.peers).with_relay(config.topology != Topology::Full),
+            links,
+        }),
+        StrategyKind::ProbabilisticResend => Box::new(ProbabilisticResend {
+            links,
+            values: HashSet::new(),
+            seen: HashMap::new(),
+            resend_ratio: config.resend_ratio,
+        }),
+        StrategyKind::Plumtree => Box::new(PlumtreeStrategy {
+            tree: Plumtree::new(&links.id, &links.peers)
+                .with_lazy_interval(config.lazy_interval)
+                .with_graft_timeout(config.graft_timeout),
+            links,
+        }),
+        StrategyKind::PushPull => Box::new(PushPull {
+            links,
+            set: AntiEntropy::new(),
+            fanout: config.fanout,
+        }),
+        StrategyKind::Merkle => Box::new(Merkle {
+            links,
+            values: HashSet::new(),
+            tree: MerkleTree::default(),
+            fanout: config.fanout,
+            relay: config.topology != Topology::Full,
+        }),
+    }
+}
+
+pub struct Links {
+    id: String,
+    peers: Vec<String>,
+    // The set encodings this node reads, most preferred first, and the one
+    // agreed on with each node that has said which it reads.
+    encodings: Vec<Encoding>,
+    negotiated: HashMap<String, Encoding>,
+}
+
+impl Links {
+    pub fn encodings(&self) -> &[Encoding] {
+        &self.encodings
+    }
+
+    // Tells `nodes` which set encodings this node reads; each answers with
+    // its own, so both sides of every pair learn what the other reads.
+    pub fn hello(&self, service: &mut Service, nodes: &[String]) -> Result<(), MaelstromError> {
+        for node in nodes {
+            let encodings = self.encodings.clone();
+            self.send(service, node, PeerPayload::Hello { encodings })?;
+        }
+
+        Ok(())
+    }
+
+    pub fn accept(&mut self, node: &str, encodings: &[Encoding]) {
+        let encoding = Encoding::negotiate(&self.encodings, encodings);
+        self.negotiated.insert(node.to_string(), encoding);
+    }
+
+    // `messages` in the encoding agreed on with `peer`, or plain before any
+    // is.
+    fn encode<C>(&self, peer: &str, messages: C) -> Encoded<C> {
+        let encoding = self.negotiated.get(peer).copied().unwrap_or_default();
+        Encoded::new(messages, encoding)
+    }
+
+    fn gossip(
+        &self,
+        service: &mut Service,
+        peer: &str,
+        messages: HashSet<usize>,
+    ) -> Result<(), MaelstromError> {
+        let messages = self.encode(peer, messages);
+        service.send_batched(peer, PeerPayload::Gossip { messages })
+    }
+
+    fn send(
+        &self,
+        service: &mut Service,
+        peer: &str,
+        payload: PeerPayload,
+    ) -> Result<(), MaelstromError> {
+        service.peer_rpc(self.id.clone(), peer.to_string(), payload)
+    }
+
+    // Sends `messages` to every peer but `except`, where they came from.
+    fn flood(
+        &self,
+        service: &mut Service,
+        messages: &HashSet<usize>,
+        except: Option<&str>,
+    ) -> Result<(), MaelstromError> {
+        if messages.is_empty() {
+            return Ok(());
+        }
+
+        for peer in &self.peers {
+            if Some(peer.as_str()) != except {
+                self.gossip(service, peer, messages.clone())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn novel(values: &HashSet<usize>, messages: &HashSet<usize>) -> HashSet<usize> {
+    messages.difference(values).copied().collect()
+}
+
+// Sends every new value to the peers once, with nothing to recover a lost
+// message.
+struct NaiveFlood {
+    links: Links,
+    values: HashSet<usize>,
+}
+
+impl GossipStrategy for NaiveFlood {
+    fn links(&mut self) -> &mut Links {
+        &mut self.links
+    }
+
+    fn values(&self) -> HashSet<usize> {
+        self.values.clone()
+    }
+
+    fn set_peers(&mut self, peers: Vec<String>) {
+        self.links.peers = peers;
+    }
+
+    fn broadcast(&mut self, service: &mut Service, value: usize) -> Result<(), MaelstromError> {
+        if self.values.insert(value) {
+            self.links.flood(service, &HashSet::from([value]), None)?;
+        }
+
+        Ok(())
+    }
+
+    fn receive(
+        &mut self,
+        service: &mut Service,
+        message: &Message<PeerPayload>,
+    ) -> Result<Option<PeerPayload>, MaelstromError> {
+        if let PeerPayload::Gossip { messages } = message.payload() {
+            let novel = novel(&self.values, messages);
+            self.values.extend(&novel);
+            self.links.flood(service, &novel, Some(message.src()))?;
+        }
+
+        Ok(None)
+    }
+}
+
+// Floods new values like the naive strategy, but resends them to each peer
+// every interval until that peer acknowledges them.
+struct AckFlood {
+    links: Links,
+    values: HashSet<usize>,
+    unacked: HashMap<String, HashSet<usize>>,
+}
+
+impl AckFlood {
+    fn flood(
+        &mut self,
+        service: &mut Service,
+        messages: HashSet<usize>,
+        except: Option<&str>,
+    ) -> Result<(), MaelstromError> {
+        for peer in &self.links.peers {
+            if Some(peer.as_str()) != except {
+                let unacked = self.unacked.entry(peer.clone()).or_default();
+                unacked.extend(&messages);
+            }
+        }
+
+        self.links.flood(service, &messages, except)
+    }
+}
+
+impl GossipStrategy for AckFlood {
+    fn links(&mut self) -> &mut Links {
+        &mut self.links
+    }
+
+    fn values(&self) -> HashSet<usize> {
+        self.values.clone()
+    }
+
+    fn set_peers(&mut self, peers: Vec<String>) {
+        self.unacked.retain(|peer, _| peers.contains(peer));
+        self.links.peers = peers;
+    }
+
+    fn broadcast(&mut self, service: &mut Service, value: usize) -> Result<(), MaelstromError> {
+        if self.values.insert(value) {
+            self.flood(service, HashSet::from([value]), None)?;
+        }
+
+        Ok(())
+    }
+
+    fn tick(&mut self, service: &mut Service) -> Result<(), MaelstromError> {
+        for (peer, unacked) in &self.unacked {
+            if !unacked.is_empty() {
+                self.links.gossip(service, peer, unacked.clone())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn receive(
+        &mut self,
+        service: &mut Service,
+        message: &Message<PeerPayload>,
+    ) -> Result<Option<PeerPayload>, MaelstromError> {
+        match message.payload() {
+            PeerPayload::Gossip { messages } => {
+                let novel = novel(&self.values, messages);
+                self.values.extend(&novel);
+                self.flood(service, novel, Some(message.src()))?;
+                Ok(Some(PeerPayload::GossipOk {
+                    messages: self.links.encode(message.src(), messages.values.clone()),
+                }))
+            }
+            PeerPayload::GossipOk { messages } => {
+                if let Some(unacked) = self.unacked.get_mut(message.src()) {
+                    unacked.retain(|value| !messages.contains(value));
+                }
+
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+// Floods new values like the naive strategy, but over reliable links, which
+// resend them until each peer has them.
+struct ReliableFlood {
+    links: Links,
+    values: HashSet<usize>,
+}
+
+impl ReliableFlood {
+    fn flood(
+        &self,
+        service: &mut Service,
+        messages: HashSet<usize>,
+        except: Option<&str>,
+    ) -> Result<(), MaelstromError> {
+        if messages.is_empty() {
+            return Ok(());
+        }
+
+        for peer in &self.links.peers {
+            if Some(peer.as_str()) != except {
+                let messages = self.links.encode(peer, messages.clone());
+                service.send_reliable(peer, PeerPayload::Gossip { messages })?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl GossipStrategy for ReliableFlood {
+    fn links(&mut self) -> &mut Links {
+        &mut self.links
+    }
+
+    fn values(&self) -> HashSet<usize> {
+        self.values.clone()
+    }
+
+    fn set_peers(&mut self, peers: Vec<String>) {
+        self.links.peers = peers;
+    }
+
+    fn broadcast(&mut self, service: &mut Service, value: usize) -> Result<(), MaelstromError> {
+        if self.values.insert(value) {
+            self.flood(service, HashSet::from([value]), None)?;
+        }
+
+        Ok(())
+    }
+
+    fn receive(
+        &mut self,
+        service: &mut Service,
+        message: &Message<PeerPayload>,
+    ) -> Result<Option<PeerPayload>, MaelstromError> {
+        if let PeerPayload::Gossip { messages } = message.payload() {
+            let novel = novel(&self.values, messages);
+            self.values.extend(&novel);
+            self.flood(service, novel, Some(message.src()))?;
+        }
+
+        Ok(None)
+    }
+}
+
+// Sends every peer all values every interval.
+struct FullState {
+    links: Links,
+    values: HashSet<usize>,
+}
+
+impl GossipStrategy for FullState {
+    fn links(&mut self) -> &mut Links {
+        &mut self.links
+    }
+
+    fn values(&self) -> HashSet<usize> {
+        self.values.clone()
+    }
+
+    fn set_peers(&mut self, peers: Vec<String>) {
+        self.links.peers = peers;
+    }
+
+    fn broadcast(&mut self, _: &mut Service, value: usize) -> Result<(), MaelstromError> {
+        self.values.insert(value);
+        Ok(())
+    }
+
+    fn tick(&mut self, service: &mut Service) -> Result<(), MaelstromError> {
+        self.links.flood(service, &self.values, None)
+    }
+
+    fn receive(
+        &mut self,
+        _: &mut Service,
+        message: &Message<PeerPayload>,
+    ) -> Result<Option<PeerPayload>, MaelstromError> {
+        if let PeerPayload::Gossip { messages } = message.payload() {
+            self.values.extend(messages.iter());
+        }
+
+        Ok(None)
+    }
+}
+
+// Sends each peer, every interval, the values added since the last sequence
+// number it acknowledged.
+struct Delta {
+    links: Links,
+    gossip: DeltaGossip<GSet<usize>>,
+}
+
+impl GossipStrategy for Delta {
+    fn links(&mut self) -> &mut Links {
+        &mut self.links
+    }
+
+    fn values(&self) -> HashSet<usize> {
+        self.gossip.state().elements().clone()
+    }
+
+    fn set_peers(&mut self, peers: Vec<String>) {
+        self.gossip.set_peers(&peers);
+        self.links.peers = peers;
+    }
+
+    fn broadcast(&mut self, _: &mut Service, value: usize) -> Result<(), MaelstromError> {
+        self.gossip.update(|values| values.insert(value));
+        Ok(())
+    }
+
+    fn tick(&mut self, service: &mut Service) -> Result<(), MaelstromError> {
+        for peer in &self.links.peers {
+            if let Some((seq, messages)) = self.gossip.pending(peer) {
+                let messages = self.links.encode(peer, messages);
+                self.links
+                    .send(service, peer, PeerPayload::Delta { seq, messages })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn receive(
+        &mut self,
+        _: &mut Service,
+        message: &Message<PeerPayload>,
+    ) -> Result<Option<PeerPayload>, MaelstromError> {
+        match message.payload() {
+            PeerPayload::Delta { seq, messages } => {
+                self.gossip.receive(message.src(), messages);
+                Ok(Some(PeerPayload::DeltaOk { seq: *seq }))
+            }
+            PeerPayload::DeltaOk { seq } => {
+                self.gossip.ack(message.src(), *seq);
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+// Sends each peer, every interval, the values it has not gossiped back, plus
+// a random share of those it has, in case what it knows changed since.
+struct ProbabilisticResend {
+    links: Links,
+    values: HashSet<usize>,
+    seen: HashMap<String, HashSet<usize>>,
+    resend_ratio: f64,
+}
+
+impl GossipStrategy for ProbabilisticResend {
+    fn links(&mut self) -> &mut Links {
+        &mut self.links
+    }
+
+    fn values(&self) -> HashSet<usize> {
+        self.values.clone()
+    }
+
+    fn set_peers(&mut self, peers: Vec<String>) {
+        self.seen.retain(|peer, _| peers.contains(peer));
+        self.links.peers = peers;
+    }
+
+    fn broadcast(&mut self, _: &mut Service, value: usize) -> Result<(), MaelstromError> {
+        self.values.insert(value);
+        Ok(())
+    }
+
+    fn tick(&mut self, service: &mut Service) -> Result<(), MaelstromError> {
+        let mut rng = rand::thread_rng();
+        for peer in &self.links.peers {
+            let seen = self.seen.get(peer);
+            let (known, mut messages): (HashSet<_>, HashSet<_>) = self
+                .values
+                .iter()
+                .partition(|value| seen.is_some_and(|seen| seen.contains(value)));
+
+            if messages.is_empty() {
+                continue;
+            }
+
+            let resend = messages.len() as f64 * self.resend_ratio / known.len().max(1) as f64;
+            messages.extend(known.iter().filter(|_| rng.gen_bool(resend.min(1.0))));
+            self.links.gossip(service, peer, messages)?;
+        }
+
+        Ok(())
+    }
+
+    fn receive(
+        &mut self,
+        _: &mut Service,
+        message: &Message<PeerPayload>,
+    ) -> Result<Option<PeerPayload>, MaelstromError> {
+        if let PeerPayload::Gossip { messages } = message.payload() {
+            self.values.extend(messages.iter());
+            self.seen
+                .entry(message.src().to_string())
+                .or_default()
+                .extend(messages.iter());
+        }
+
+        Ok(None)
+    }
+}
+
+// Pushes new values along Plumtree spanning trees and announces them lazily
+// to the other peers. On a full mesh every node has all others as lazy peers,
+// so it is best run on a sparse topology.
+struct PlumtreeStrategy {
+    links: Links,
+    tree: Plumtree<usize>,
+}
+
+impl PlumtreeStrategy {
+    fn send(
+        &self,
+        service: &mut Service,
+        outgoing: Vec<(String, Outgoing<usize>)>,
+    ) -> Result<(), MaelstromError> {
+        for (peer, outgoing) in outgoing {
+            let payload = match outgoing {
+                Outgoing::Gossip { root, values } => PeerPayload::Push {
+                    root,
+                    messages: self.links.encode(&peer, values),
+                },
+                Outgoing::IHave(messages) => PeerPayload::IHave { messages },
+                Outgoing::Graft { root, values } => PeerPayload::Graft {
+                    root,
+                    messages: self.links.encode(&peer, values),
+                },
+                Outgoing::Prune { root } => PeerPayload::Prune { root },
+            };
+
+            self.links.send(service, &peer, payload)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl GossipStrategy for PlumtreeStrategy {
+    fn links(&mut self) -> &mut Links {
+        &mut self.links
+    }
+
+    fn values(&self) -> HashSet<usize> {
+        self.tree.received().clone()
+    }
+
+    fn set_peers(&mut self, peers: Vec<String>) {
+        self.tree.set_peers(&peers);
+        self.links.peers = peers;
+    }
+
+    fn broadcast(&mut self, service: &mut Service, value: usize) -> Result<(), MaelstromError> {
+        let outgoing = self.tree.broadcast(value);
+        self.send(service, outgoing)
+    }
+
+    fn tick(&mut self, service: &mut Service) -> Result<(), MaelstromError> {
+        let outgoing = self.tree.tick();
+        self.send(service, outgoing)
+    }
+
+    fn receive(
+        &mut self,
+        service: &mut Service,
+        message: &Message<PeerPayload>,
+    ) -> Result<Option<PeerPayload>, MaelstromError> {
+        let peer = message.src();
+        match message.payload() {
+            PeerPayload::Push { root, messages } => {
+                let outgoing = self.tree.gossip(peer, root, messages);
+                self.send(service, outgoing)?;
+                Ok(None)
+            }
+            PeerPayload::IHave { messages } => {
+                self.tree.ihave(peer, messages);
+                let messages = messages.iter().map(|(_, value)| *value).collect();
+                Ok(Some(PeerPayload::IHaveOk {
+                    messages: self.links.encode(peer, messages),
+                }))
+            }
+            PeerPayload::IHaveOk { messages } => {
+                self.tree.ihave_ok(peer, messages);
+                Ok(None)
+            }
+            PeerPayload::Graft { root, messages } => {
+                let messages = self.tree.graft(peer, root, messages);
+                Ok(Some(PeerPayload::GraftOk {
+                    root: root.clone(),
+                    messages: self.links.encode(peer, messages),
+                }))
+            }
+            PeerPayload::GraftOk { root, messages } => {
+                let outgoing = self.tree.graft_ok(peer, root, messages);
+                self.send(service, outgoing)?;
+                Ok(None)
+            }
+            PeerPayload::Prune { root } => {
+                self.tree.prune(peer, root);
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+// Sends a few random peers a digest of the values every interval, and
+// exchanges values only where a peer's differ. Values spread in a logarithmic
+// number of rounds, while converged nodes only ever send digests.
+struct PushPull {
+    links: Links,
+    set: AntiEntropy<usize>,
+    fanout: usize,
+}
+
+impl GossipStrategy for PushPull {
+    fn links(&mut self) -> &mut Links {
+        &mut self.links
+    }
+
+    fn values(&self) -> HashSet<usize> {
+        self.set.values().iter().copied().collect()
+    }
+
+    fn set_peers(&mut self, peers: Vec<String>) {
+        self.links.peers = peers;
+    }
+
+    fn broadcast(&mut self, _: &mut Service, value: usize) -> Result<(), MaelstromError> {
+        self.set.insert(value);
+        Ok(())
+    }
+
+    fn tick(&mut self, service: &mut Service) -> Result<(), MaelstromError> {
+        let digest = self.set.digest();
+        let peers = self
+            .links
+            .peers
+            .choose_multiple(&mut rand::thread_rng(), self.fanout);
+
+        for peer in peers {
+            let digest = digest.clone();
+            self.links
+                .send(service, peer, PeerPayload::Digest { digest })?;
+        }
+
+        Ok(())
+    }
+
+    fn receive(
+        &mut self,
+        _: &mut Service,
+        message: &Message<PeerPayload>,
+    ) -> Result<Option<PeerPayload>, MaelstromError> {
+        let peer = message.src();
+        match message.payload() {
+            PeerPayload::Digest { digest } => {
+                Ok(self.set.compare(digest).map(|(spans, messages)| {
+                    let messages = self.links.encode(peer, messages);
+                    PeerPayload::Reconcile { spans, messages }
+                }))
+            }
+            PeerPayload::Reconcile { spans, messages } => {
+                let messages = self.set.reconcile(spans, messages.values.clone());
+                Ok(Some(PeerPayload::ReconcileOk {
+                    messages: self.links.encode(peer, messages),
+                }))
+            }
+            PeerPayload::ReconcileOk { messages } => {
+                self.set.merge(messages.iter().copied());
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+// Pushes new values to the peers once, and repairs whatever that missed by
+// reconciling with a few random peers every interval, walking their Merkle
+// trees down to the buckets that differ. After a partition heals only the
+// values missing on either side are sent.
+struct Merkle {
+    links: Links,
+    values: HashSet<usize>,
+    tree: MerkleTree<usize>,
+    fanout: usize,
+    relay: bool,
+}
+
+impl Merkle {
+    fn merge(&mut self, messages: impl IntoIterator<Item = usize>) -> HashSet<usize> {
+        let mut novel = HashSet::new();
+        for value in messages {
+            if self.values.insert(value) {
+                self.tree.insert(value, &());
+                novel.insert(value);
+            }
+        }
+
+        novel
+    }
+}
+
+impl GossipStrategy for Merkle {
+    fn links(&mut self) -> &mut Links {
+        &mut self.links
+    }
+
+    fn values(&self) -> HashSet<usize> {
+        self.values.clone()
+    }
+
+    fn set_peers(&mut self, peers: Vec<String>) {
+        self.links.peers = peers;
+    }
+
+    fn broadcast(&mut self, service: &mut Service, value: usize) -> Result<(), MaelstromError> {
+        let novel = self.merge([value]);
+        self.links.flood(service, &novel, None)
+    }
+
+    fn tick(&mut self, service: &mut Service) -> Result<(), MaelstromError> {
+        let peers = self
+            .links
+            .peers
+            .choose_multiple(&mut rand::thread_rng(), self.fanout);
+
+        for peer in peers {
+            let hashes = self.tree.root();
+            self.links
+                .send(service, peer, PeerPayload::Hashes { hashes })?;
+        }
+
+        Ok(())
+    }
+
+    fn receive(
+        &mut self,
+        service: &mut Service,
+        message: &Message<PeerPayload>,
+    ) -> Result<Option<PeerPayload>, MaelstromError> {
+        let peer = message.src();
+        match message.payload() {
+            PeerPayload::Gossip { messages } => {
+                let novel = self.merge(messages.iter().copied());
+                if self.relay {
+                    self.links.flood(service, &novel, Some(peer))?;
+                }
+
+                Ok(None)
+            }
+            PeerPayload::Hashes { hashes } => Ok(match self.tree.compare(hashes) {
+                Step::InSync => None,
+                Step::Descend(hashes) => Some(PeerPayload::Hashes { hashes }),
+                Step::Exchange(buckets) => {
+                    let messages = self.tree.keys(&buckets).copied().collect();
+                    let messages = self.links.encode(peer, messages);
+                    Some(PeerPayload::Buckets { buckets, messages })
+                }
+            }),
+            PeerPayload::Buckets { buckets, messages } => {
+                let theirs = messages.iter().collect::<HashSet<_>>();
+                let missing = self
+                    .tree
+                    .keys(buckets)
+                    .filter(|value| !theirs.contains(value))
+                    .copied()
+                    .collect();
+
+                self.merge(messages.iter().copied());
+                Ok(Some(PeerPayload::BucketsOk {
+                    messages: self.links.encode(peer, missing),
+                }))
+            }
+            PeerPayload::BucketsOk { messages } => {
+                self.merge(messages.iter().copied());
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+}
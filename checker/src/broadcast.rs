@@ -0,0 +1,245 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt::Display,
+    time::Duration,
+};
+
+use crate::{
+    history::{History, MessageLog, Operation},
+    stats::Percentiles,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct Thresholds {
+    pub max_lost: usize,
+    pub max_stale_reads: Option<usize>,
+    pub max_msgs_per_op: Option<f64>,
+    pub max_median_latency: Option<Duration>,
+    pub max_latency: Option<Duration>,
+}
+
+impl Thresholds {
+    pub fn challenge_3d() -> Self {
+        Self {
+            max_msgs_per_op: Some(30.0),
+            max_median_latency: Some(Duration::from_millis(400)),
+            max_latency: Some(Duration::from_millis(600)),
+            ..Default::default()
+        }
+    }
+
+    pub fn challenge_3e() -> Self {
+        Self {
+            max_msgs_per_op: Some(20.0),
+            max_median_latency: Some(Duration::from_secs(1)),
+            max_latency: Some(Duration::from_secs(2)),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BroadcastReport {
+    pub operations: usize,
+    pub acknowledged: usize,
+    pub lost: BTreeSet<usize>,
+    pub unexpected: BTreeSet<usize>,
+    pub stale_reads: usize,
+    pub server_messages: usize,
+    pub msgs_per_op: f64,
+    pub stable_latencies: Percentiles,
+}
+
+impl BroadcastReport {
+    pub fn violations(&self, thresholds: &Thresholds) -> Vec<String> {
+        let mut violations = vec![];
+        if self.lost.len() > thresholds.max_lost {
+            violations.push(format!(
+                "{} acknowledged broadcasts were lost (allowed {})",
+                self.lost.len(),
+                thresholds.max_lost
+            ));
+        }
+
+        if !self.unexpected.is_empty() {
+            violations.push(format!(
+                "{} values were read but never broadcast",
+                self.unexpected.len()
+            ));
+        }
+
+        if let Some(max) = thresholds.max_stale_reads {
+            if self.stale_reads > max {
+                violations.push(format!("{} stale reads (allowed {max})", self.stale_reads));
+            }
+        }
+
+        if let Some(max) = thresholds.max_msgs_per_op {
+            if self.msgs_per_op > max {
+                violations.push(format!(
+                    "{:.2} server msgs-per-op (allowed {max:.2})",
+                    self.msgs_per_op
+                ));
+            }
+        }
+
+        if let (Some(max), Some(median)) = (
+            thresholds.max_median_latency,
+            self.stable_latencies.median(),
+        ) {
+            if median > max {
+                violations.push(format!(
+                    "median stable latency {}ms (allowed {}ms)",
+                    median.as_millis(),
+                    max.as_millis()
+                ));
+            }
+        }
+
+        if let (Some(max), Some(latency)) = (thresholds.max_latency, self.stable_latencies.max()) {
+            if latency > max {
+                violations.push(format!(
+                    "max stable latency {}ms (allowed {}ms)",
+                    latency.as_millis(),
+                    max.as_millis()
+                ));
+            }
+        }
+
+        violations
+    }
+}
+
+impl Display for BroadcastReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "operations:       {}", self.operations)?;
+        writeln!(f, "acknowledged:     {}", self.acknowledged)?;
+        writeln!(f, "lost:             {}", self.lost.len())?;
+        writeln!(f, "unexpected:       {}", self.unexpected.len())?;
+        writeln!(f, "stale reads:      {}", self.stale_reads)?;
+        writeln!(f, "server messages:  {}", self.server_messages)?;
+        writeln!(f, "msgs-per-op:      {:.2}", self.msgs_per_op)?;
+        write!(f, "stable latencies: {}", self.stable_latencies)
+    }
+}
+
+struct Read {
+    invoked_at: u64,
+    completed_at: u64,
+    values: HashSet<usize>,
+}
+
+fn read_of(operation: &Operation) -> Option<Read> {
+    if operation.f != "read" || !operation.is_ok() {
+        return None;
+    }
+
+    let values = serde_json::from_value(operation.output.clone()?).ok()?;
+    Some(Read {
+        invoked_at: operation.invoked_at,
+        completed_at: operation.completed_at?,
+        values,
+    })
+}
+
+pub fn check(history: &History, log: &MessageLog) -> BroadcastReport {
+    let operations = history.operations();
+
+    let mut invoked = HashMap::new();
+    let mut acknowledged = HashMap::new();
+    for operation in operations
+        .iter()
+        .filter(|operation| operation.f == "broadcast")
+    {
+        let Some(value) = operation.input.as_u64().map(|value| value as usize) else {
+            continue;
+        };
+
+        invoked.entry(value).or_insert(operation.invoked_at);
+        if let (true, Some(completed_at)) = (operation.is_ok(), operation.completed_at) {
+            acknowledged.entry(value).or_insert(completed_at);
+        }
+    }
+
+    let mut final_reads: HashMap<String, &Read> = HashMap::new();
+    let reads = operations
+        .iter()
+        .filter_map(|operation| {
+            read_of(operation).map(|read| {
+                let node = operation
+                    .node
+                    .clone()
+                    .unwrap_or_else(|| format!("p{}", operation.process));
+                (node, read)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for (node, read) in &reads {
+        let latest = final_reads.entry(node.clone()).or_insert(read);
+        if read.completed_at > latest.completed_at {
+            *latest = read;
+        }
+    }
+
+    let lost = acknowledged
+        .keys()
+        .filter(|value| {
+            final_reads.is_empty()
+                || final_reads
+                    .values()
+                    .any(|read| !read.values.contains(value))
+        })
+        .copied()
+        .collect::<BTreeSet<_>>();
+
+    let unexpected = reads
+        .iter()
+        .flat_map(|(_, read)| read.values.iter())
+        .filter(|value| !invoked.contains_key(value))
+        .copied()
+        .collect::<BTreeSet<_>>();
+
+    let stale_reads = reads
+        .iter()
+        .filter(|(_, read)| {
+            acknowledged.iter().any(|(value, &completed_at)| {
+                completed_at < read.invoked_at && !read.values.contains(value)
+            })
+        })
+        .count();
+
+    let stable_latencies = acknowledged
+        .keys()
+        .filter(|value| !lost.contains(value))
+        .map(|value| {
+            let broadcast_at = invoked[value];
+            let stable_at = reads
+                .iter()
+                .filter(|(_, read)| read.invoked_at >= broadcast_at && !read.values.contains(value))
+                .map(|(_, read)| read.completed_at)
+                .max()
+                .unwrap_or(broadcast_at);
+
+            Duration::from_nanos(stable_at - broadcast_at)
+        })
+        .collect::<Vec<_>>();
+
+    let server_messages = log.server_messages();
+    let msgs_per_op = if operations.is_empty() {
+        0.0
+    } else {
+        server_messages as f64 / operations.len() as f64
+    };
+
+    BroadcastReport {
+        operations: operations.len(),
+        acknowledged: acknowledged.len(),
+        lost,
+        unexpected,
+        stale_reads,
+        server_messages,
+        msgs_per_op,
+        stable_latencies: Percentiles::new(stable_latencies),
+    }
+}
@@ -0,0 +1,172 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    broadcast::Thresholds,
+    history::{EventKind, History, MessageLog, Operation},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct CounterReport {
+    pub acknowledged: i64,
+    pub attempted: i64,
+    pub reads: usize,
+    pub stale_reads: usize,
+    pub operations: usize,
+    pub internal_messages: usize,
+    pub msgs_per_op: f64,
+    pub final_reads: Vec<i64>,
+    pub problems: Vec<String>,
+}
+
+impl CounterReport {
+    pub fn violations(&self, thresholds: &Thresholds) -> Vec<String> {
+        let mut violations = self.problems.clone();
+        if let Some(max) = thresholds.max_stale_reads {
+            if self.stale_reads > max {
+                violations.push(format!(
+                    "{} reads missed acknowledged adds (allowed {max})",
+                    self.stale_reads
+                ));
+            }
+        }
+
+        if let Some(max) = thresholds.max_msgs_per_op {
+            if self.msgs_per_op > max {
+                violations.push(format!(
+                    "{:.2} msgs-per-op (allowed {max:.2})",
+                    self.msgs_per_op
+                ));
+            }
+        }
+
+        violations
+    }
+}
+
+impl Display for CounterReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "acknowledged sum: {}", self.acknowledged)?;
+        writeln!(f, "attempted sum:    {}", self.attempted)?;
+        writeln!(
+            f,
+            "reads:            {} ({} stale)",
+            self.reads, self.stale_reads
+        )?;
+        writeln!(
+            f,
+            "msgs-per-op:      {:.2} ({} internal messages over {} operations)",
+            self.msgs_per_op, self.internal_messages, self.operations
+        )?;
+        write!(f, "final reads:      {:?}", self.final_reads)
+    }
+}
+
+fn delta(operation: &Operation) -> i64 {
+    operation.input.as_i64().unwrap_or_default()
+}
+
+fn node_of(operation: &Operation) -> String {
+    operation
+        .node
+        .clone()
+        .unwrap_or_else(|| operation.process.to_string())
+}
+
+// Checks a history of `add` and `read` operations. A read is stale unless it
+// falls between the adds certainly applied before it began and the adds that
+// may have been applied before it ended. As in Maelstrom, only the final read
+// on each node, issued after the last add, must be within those bounds, and
+// the final reads must all agree.
+// Messages per operation count traffic to key-value services too, so that
+// designs built on them compare fairly with gossip-only ones.
+pub fn check(history: &History, messages: &MessageLog) -> CounterReport {
+    let operations = history.operations();
+    let (adds, reads): (Vec<_>, Vec<_>) = operations
+        .iter()
+        .filter(|operation| operation.outcome != EventKind::Fail)
+        .partition(|operation| operation.f == "add");
+
+    let internal_messages = messages.internal_messages();
+    let mut report = CounterReport {
+        operations: operations.len(),
+        internal_messages,
+        msgs_per_op: internal_messages as f64 / operations.len().max(1) as f64,
+        acknowledged: adds
+            .iter()
+            .filter(|add| add.is_ok())
+            .map(|add| delta(add))
+            .sum(),
+        attempted: adds.iter().map(|add| delta(add)).sum(),
+        ..CounterReport::default()
+    };
+
+    // An add that never completed may still be applied at any time, but
+    // counting it from when it was invoked keeps a timed out add from leaving
+    // no read late enough to be final.
+    let last_add = adds
+        .iter()
+        .map(|add| add.completed_at.unwrap_or(add.invoked_at))
+        .max()
+        .unwrap_or_default();
+
+    let mut last_reads = HashMap::new();
+    for read in reads.iter().filter(|read| read.is_ok()) {
+        let last = last_reads.entry(node_of(read)).or_insert(read.invoked_at);
+        *last = (*last).max(read.invoked_at);
+    }
+
+    for read in reads.iter().filter(|read| read.is_ok()) {
+        let Some(value) = read.output.as_ref().and_then(|value| value.as_i64()) else {
+            report.problems.push(format!(
+                "read by process {} returned no value",
+                read.process
+            ));
+            continue;
+        };
+
+        let completed_at = read.completed_at.unwrap_or(u64::MAX);
+        let (mut lower, mut upper) = (0, 0);
+        for add in &adds {
+            let delta = delta(add);
+            let certain = add.is_ok() && add.completed_at.is_some_and(|at| at < read.invoked_at);
+            if certain {
+                lower += delta;
+                upper += delta;
+            } else if add.invoked_at < completed_at {
+                if delta < 0 {
+                    lower += delta;
+                } else {
+                    upper += delta;
+                }
+            }
+        }
+
+        report.reads += 1;
+        let in_bounds = lower <= value && value <= upper;
+        if !in_bounds {
+            report.stale_reads += 1;
+        }
+
+        if read.invoked_at > last_add && last_reads[&node_of(read)] == read.invoked_at {
+            report.final_reads.push(value);
+            if !in_bounds {
+                report.problems.push(format!(
+                    "final read by process {} returned {value}, outside [{lower}, {upper}]",
+                    read.process
+                ));
+            }
+        }
+    }
+
+    if report.final_reads.is_empty() {
+        report
+            .problems
+            .push("no read began after the last add, so convergence was not checked".into());
+    } else if report.final_reads.windows(2).any(|pair| pair[0] != pair[1]) {
+        report
+            .problems
+            .push(format!("final reads disagree: {:?}", report.final_reads));
+    }
+
+    report
+}
@@ -0,0 +1,334 @@
+use crate::error::CheckerError;
+
+// The subset of EDN that Jepsen writes to results.edn. Maps keep their entries
+// in order as pairs, since keys may be floats.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edn {
+    Nil,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Char(char),
+    Keyword(String),
+    Symbol(String),
+    List(Vec<Edn>),
+    Vector(Vec<Edn>),
+    Map(Vec<(Edn, Edn)>),
+    Set(Vec<Edn>),
+    Tagged(String, Box<Edn>),
+}
+
+impl Edn {
+    // Looks up a keyword key, written without the leading colon.
+    pub fn get(&self, key: &str) -> Option<&Edn> {
+        let Self::Map(entries) = self else {
+            return None;
+        };
+
+        entries
+            .iter()
+            .find(|(k, _)| matches!(k, Self::Keyword(keyword) if keyword == key))
+            .map(|(_, value)| value)
+    }
+
+    pub fn get_in(&self, path: &[&str]) -> Option<&Edn> {
+        path.iter().try_fold(self, |edn, key| edn.get(key))
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Integer(value) => Some(*value as f64),
+            Self::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn entries(&self) -> &[(Edn, Edn)] {
+        match self {
+            Self::Map(entries) => entries,
+            _ => &[],
+        }
+    }
+
+    pub fn items(&self) -> &[Edn] {
+        match self {
+            Self::List(items) | Self::Vector(items) | Self::Set(items) => items,
+            _ => &[],
+        }
+    }
+}
+
+pub fn parse(input: &str) -> Result<Edn, CheckerError> {
+    let mut parser = Parser {
+        input: input.as_bytes(),
+        position: 0,
+    };
+
+    let edn = parser.value()?;
+    parser.skip_whitespace();
+    if parser.position < parser.input.len() {
+        return Err(parser.error());
+    }
+
+    Ok(edn)
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+fn is_delimiter(byte: u8) -> bool {
+    byte.is_ascii_whitespace() || b",()[]{}\";".contains(&byte)
+}
+
+impl Parser<'_> {
+    fn error(&self) -> CheckerError {
+        CheckerError::EdnParseError(self.position)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(byte) = self.peek() {
+            match byte {
+                b';' => {
+                    while self.peek().is_some_and(|byte| byte != b'\n') {
+                        self.position += 1;
+                    }
+                }
+                byte if byte.is_ascii_whitespace() || byte == b',' => self.position += 1,
+                _ => break,
+            }
+        }
+    }
+
+    fn token(&mut self) -> &str {
+        let start = self.position;
+        while self.peek().is_some_and(|byte| !is_delimiter(byte)) {
+            self.position += 1;
+        }
+
+        std::str::from_utf8(&self.input[start..self.position]).unwrap_or_default()
+    }
+
+    fn sequence(&mut self, close: u8) -> Result<Vec<Edn>, CheckerError> {
+        self.position += 1;
+        let mut items = vec![];
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(byte) if byte == close => {
+                    self.position += 1;
+                    return Ok(items);
+                }
+                Some(_) => {
+                    if let Some(item) = self.element()? {
+                        items.push(item);
+                    }
+                }
+                None => return Err(self.error()),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<Edn, CheckerError> {
+        self.position += 1;
+        let mut bytes = vec![];
+        loop {
+            match self.peek().ok_or_else(|| self.error())? {
+                b'"' => break,
+                b'\\' => {
+                    self.position += 1;
+                    let escaped = match self.peek().ok_or_else(|| self.error())? {
+                        b'n' => b'\n',
+                        b't' => b'\t',
+                        b'r' => b'\r',
+                        byte => byte,
+                    };
+                    bytes.push(escaped);
+                }
+                byte => bytes.push(byte),
+            }
+
+            self.position += 1;
+        }
+
+        self.position += 1;
+        String::from_utf8(bytes)
+            .map(Edn::String)
+            .map_err(|_| self.error())
+    }
+
+    fn number_or_symbol(&mut self) -> Result<Edn, CheckerError> {
+        let token = self.token().to_string();
+        let numeric = token.trim_end_matches(['N', 'M']);
+        if let Ok(value) = numeric.parse::<i64>() {
+            return Ok(Edn::Integer(value));
+        }
+
+        if let Ok(value) = numeric.parse::<f64>() {
+            return Ok(Edn::Float(value));
+        }
+
+        if let Some((numerator, denominator)) = numeric.split_once('/') {
+            if let (Ok(numerator), Ok(denominator)) =
+                (numerator.parse::<f64>(), denominator.parse::<f64>())
+            {
+                return Ok(Edn::Float(numerator / denominator));
+            }
+        }
+
+        match token.as_str() {
+            "" => Err(self.error()),
+            "nil" => Ok(Edn::Nil),
+            "true" => Ok(Edn::Bool(true)),
+            "false" => Ok(Edn::Bool(false)),
+            "##Inf" => Ok(Edn::Float(f64::INFINITY)),
+            "##-Inf" => Ok(Edn::Float(f64::NEG_INFINITY)),
+            "##NaN" => Ok(Edn::Float(f64::NAN)),
+            _ => Ok(Edn::Symbol(token)),
+        }
+    }
+
+    // Parses the next element, returning `None` for a `#_` discarded form.
+    fn element(&mut self) -> Result<Option<Edn>, CheckerError> {
+        self.skip_whitespace();
+        let edn = match self.peek().ok_or_else(|| self.error())? {
+            b'(' => Edn::List(self.sequence(b')')?),
+            b'[' => Edn::Vector(self.sequence(b']')?),
+            b'{' => {
+                let items = self.sequence(b'}')?;
+                if items.len() % 2 != 0 {
+                    return Err(self.error());
+                }
+
+                let mut items = items.into_iter();
+                let mut entries = vec![];
+                while let (Some(key), Some(value)) = (items.next(), items.next()) {
+                    entries.push((key, value));
+                }
+
+                Edn::Map(entries)
+            }
+            b'"' => self.string()?,
+            b':' => {
+                self.position += 1;
+                Edn::Keyword(self.token().to_string())
+            }
+            b'\\' => {
+                self.position += 1;
+                let start = self.position;
+                let token = self.token().to_string();
+                let character = match token.as_str() {
+                    "" => {
+                        self.position += 1;
+                        *self.input.get(start).ok_or_else(|| self.error())? as char
+                    }
+                    "newline" => '\n',
+                    "space" => ' ',
+                    "tab" => '\t',
+                    "return" => '\r',
+                    _ => token.chars().next().ok_or_else(|| self.error())?,
+                };
+                Edn::Char(character)
+            }
+            b'#' => match self.input.get(self.position + 1) {
+                Some(b'{') => {
+                    self.position += 1;
+                    Edn::Set(self.sequence(b'}')?)
+                }
+                Some(b'_') => {
+                    self.position += 2;
+                    self.value()?;
+                    return Ok(None);
+                }
+                Some(b'#') => self.number_or_symbol()?,
+                _ => {
+                    self.position += 1;
+                    let tag = self.token().to_string();
+                    Edn::Tagged(tag, Box::new(self.value()?))
+                }
+            },
+            b')' | b']' | b'}' => return Err(self.error()),
+            _ => self.number_or_symbol()?,
+        };
+
+        Ok(Some(edn))
+    }
+
+    fn value(&mut self) -> Result<Edn, CheckerError> {
+        loop {
+            if let Some(edn) = self.element()? {
+                return Ok(edn);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_scalars() {
+        assert_eq!(parse("nil").unwrap(), Edn::Nil);
+        assert_eq!(parse("true").unwrap(), Edn::Bool(true));
+        assert_eq!(parse("-42").unwrap(), Edn::Integer(-42));
+        assert_eq!(parse("7N").unwrap(), Edn::Integer(7));
+        assert_eq!(parse("0.5").unwrap(), Edn::Float(0.5));
+        assert_eq!(parse("3/4").unwrap(), Edn::Float(0.75));
+        assert_eq!(parse("##Inf").unwrap(), Edn::Float(f64::INFINITY));
+        assert_eq!(parse(":valid?").unwrap(), Edn::Keyword("valid?".into()));
+        assert_eq!(parse("\\newline").unwrap(), Edn::Char('\n'));
+        assert_eq!(
+            parse(r#""a \"quoted\"\n""#).unwrap(),
+            Edn::String("a \"quoted\"\n".into())
+        );
+    }
+
+    #[test]
+    fn parses_collections_comments_and_discards() {
+        let edn = parse("[1, (2 3) #{:a} #_ ignored ; to the end of the line\n {:b nil}]").unwrap();
+        assert_eq!(
+            edn,
+            Edn::Vector(vec![
+                Edn::Integer(1),
+                Edn::List(vec![Edn::Integer(2), Edn::Integer(3)]),
+                Edn::Set(vec![Edn::Keyword("a".into())]),
+                Edn::Map(vec![(Edn::Keyword("b".into()), Edn::Nil)]),
+            ])
+        );
+    }
+
+    #[test]
+    fn parses_tagged_values() {
+        assert_eq!(
+            parse(r#"#jepsen.history.Op {:index 0}"#).unwrap(),
+            Edn::Tagged(
+                "jepsen.history.Op".into(),
+                Box::new(Edn::Map(vec![(
+                    Edn::Keyword("index".into()),
+                    Edn::Integer(0)
+                )]))
+            )
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        for input in ["", "[1 2", "{:a}", "]", "\"open", "1 2"] {
+            assert!(parse(input).is_err(), "{input:?} should not parse");
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub enum CheckerError {
+    IOError,
+    HistoryParseError(usize),
+    MessageLogParseError(usize),
+    EdnParseError(usize),
+}
+
+impl Display for CheckerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IOError => write!(f, "[checker error] - io error"),
+            Self::HistoryParseError(line) => {
+                write!(f, "[checker error] - failed to parse history line {line}")
+            }
+            Self::MessageLogParseError(line) => {
+                write!(
+                    f,
+                    "[checker error] - failed to parse message log line {line}"
+                )
+            }
+            Self::EdnParseError(offset) => {
+                write!(f, "[checker error] - failed to parse edn at byte {offset}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CheckerError {}
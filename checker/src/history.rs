@@ -0,0 +1,251 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, Write},
+    time::Duration,
+};
+
+use maelstrom::message::Message;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::CheckerError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Invoke,
+    Ok,
+    Fail,
+    Info,
+}
+
+// One line of a recorded history, in the same shape Jepsen uses: a client
+// process invokes `f` with `value`, then the same process later completes it
+// with `ok`, `fail` or `info` (indeterminate). `time` is in nanoseconds since
+// the start of the run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub process: usize,
+    #[serde(rename = "type")]
+    pub kind: EventKind,
+    pub f: String,
+    #[serde(default)]
+    pub value: Value,
+    pub time: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Operation {
+    pub process: usize,
+    pub f: String,
+    pub node: Option<String>,
+    pub input: Value,
+    pub output: Option<Value>,
+    pub outcome: EventKind,
+    pub invoked_at: u64,
+    pub completed_at: Option<u64>,
+}
+
+impl Operation {
+    pub fn is_ok(&self) -> bool {
+        self.outcome == EventKind::Ok
+    }
+
+    pub fn latency(&self) -> Option<Duration> {
+        self.completed_at
+            .map(|completed_at| Duration::from_nanos(completed_at - self.invoked_at))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    events: Vec<Event>,
+}
+
+impl History {
+    pub fn new(events: Vec<Event>) -> Self {
+        Self { events }
+    }
+
+    pub fn from_operations(operations: &[Operation]) -> Self {
+        let mut events = vec![];
+        for operation in operations {
+            events.push(Event {
+                process: operation.process,
+                kind: EventKind::Invoke,
+                f: operation.f.clone(),
+                value: operation.input.clone(),
+                time: operation.invoked_at,
+                node: operation.node.clone(),
+            });
+
+            if let Some(completed_at) = operation.completed_at {
+                events.push(Event {
+                    process: operation.process,
+                    kind: operation.outcome,
+                    f: operation.f.clone(),
+                    value: operation.output.clone().unwrap_or_default(),
+                    time: completed_at,
+                    node: operation.node.clone(),
+                });
+            }
+        }
+
+        events.sort_by_key(|event| (event.time, event.kind != EventKind::Invoke));
+        Self { events }
+    }
+
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    pub fn push(&mut self, event: Event) {
+        self.events.push(event);
+    }
+
+    pub fn read_from(input: impl BufRead) -> Result<Self, CheckerError> {
+        let mut events = vec![];
+        for (index, line) in input.lines().enumerate() {
+            let line = line.map_err(|_| CheckerError::IOError)?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let event = serde_json::from_str(&line)
+                .map_err(|_| CheckerError::HistoryParseError(index + 1))?;
+            events.push(event);
+        }
+
+        Ok(Self { events })
+    }
+
+    pub fn write_to(&self, output: &mut impl Write) -> Result<(), CheckerError> {
+        for event in &self.events {
+            serde_json::to_writer(&mut *output, event).map_err(|_| CheckerError::IOError)?;
+            output.write_all(b"\n").map_err(|_| CheckerError::IOError)?;
+        }
+
+        Ok(())
+    }
+
+    // Pairs every invocation with the next completion from the same process.
+    // Invocations that never complete are reported as `info`, since they may
+    // or may not have taken effect.
+    pub fn operations(&self) -> Vec<Operation> {
+        let mut operations: Vec<Operation> = vec![];
+        let mut pending = HashMap::new();
+        for event in &self.events {
+            match event.kind {
+                EventKind::Invoke => {
+                    pending.insert(event.process, operations.len());
+                    operations.push(Operation {
+                        process: event.process,
+                        f: event.f.clone(),
+                        node: event.node.clone(),
+                        input: event.value.clone(),
+                        output: None,
+                        outcome: EventKind::Info,
+                        invoked_at: event.time,
+                        completed_at: None,
+                    });
+                }
+                kind => {
+                    let Some(index) = pending.remove(&event.process) else {
+                        continue;
+                    };
+
+                    let operation = &mut operations[index];
+                    operation.outcome = kind;
+                    if kind != EventKind::Info {
+                        operation.output = Some(event.value.clone());
+                        operation.completed_at = Some(event.time);
+                    }
+
+                    if operation.node.is_none() {
+                        operation.node = event.node.clone();
+                    }
+                }
+            }
+        }
+
+        operations
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedMessage {
+    pub time: u64,
+    pub message: Message<Value>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MessageLog {
+    entries: Vec<LoggedMessage>,
+}
+
+impl MessageLog {
+    pub fn new(entries: Vec<LoggedMessage>) -> Self {
+        Self { entries }
+    }
+
+    pub fn entries(&self) -> &[LoggedMessage] {
+        &self.entries
+    }
+
+    pub fn push(&mut self, entry: LoggedMessage) {
+        self.entries.push(entry);
+    }
+
+    pub fn read_from(input: impl BufRead) -> Result<Self, CheckerError> {
+        let mut entries = vec![];
+        for (index, line) in input.lines().enumerate() {
+            let line = line.map_err(|_| CheckerError::IOError)?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry = serde_json::from_str(&line)
+                .map_err(|_| CheckerError::MessageLogParseError(index + 1))?;
+            entries.push(entry);
+        }
+
+        Ok(Self { entries })
+    }
+
+    pub fn write_to(&self, output: &mut impl Write) -> Result<(), CheckerError> {
+        for entry in &self.entries {
+            serde_json::to_writer(&mut *output, entry).map_err(|_| CheckerError::IOError)?;
+            output.write_all(b"\n").map_err(|_| CheckerError::IOError)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn server_messages(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| is_server(entry.message.src()) && is_server(entry.message.dest()))
+            .count()
+    }
+
+    // Messages between nodes and services such as seq-kv, leaving out client
+    // traffic.
+    pub fn internal_messages(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| !is_client(entry.message.src()) && !is_client(entry.message.dest()))
+            .count()
+    }
+}
+
+pub fn is_client(id: &str) -> bool {
+    id.strip_prefix('c')
+        .is_some_and(|index| !index.is_empty() && index.chars().all(|c| c.is_ascii_digit()))
+}
+
+pub fn is_server(id: &str) -> bool {
+    id.strip_prefix('n')
+        .is_some_and(|index| !index.is_empty() && index.chars().all(|c| c.is_ascii_digit()))
+}
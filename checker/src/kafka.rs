@@ -0,0 +1,212 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+};
+
+use serde_json::Value;
+
+use crate::history::{History, Operation};
+
+#[derive(Debug, Clone, Default)]
+pub struct KafkaReport {
+    pub sends: usize,
+    pub acknowledged: usize,
+    pub polls: usize,
+    pub lost: usize,
+    pub problems: Vec<String>,
+}
+
+impl Display for KafkaReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "sends:        {}", self.sends)?;
+        writeln!(f, "acknowledged: {}", self.acknowledged)?;
+        writeln!(f, "polls:        {}", self.polls)?;
+        write!(f, "lost:         {}", self.lost)
+    }
+}
+
+struct Send {
+    key: String,
+    msg: u64,
+    offset: u64,
+    invoked_at: u64,
+    completed_at: u64,
+}
+
+struct Poll {
+    invoked_at: u64,
+    from: u64,
+    msgs: BTreeMap<u64, u64>,
+}
+
+// Acknowledged sends complete with `[key, msg, offset]`.
+fn send_of(operation: &Operation) -> Option<Send> {
+    let output = operation.output.as_ref()?.as_array()?;
+    Some(Send {
+        key: output.first()?.as_str()?.to_string(),
+        msg: output.get(1)?.as_u64()?,
+        offset: output.get(2)?.as_u64()?,
+        invoked_at: operation.invoked_at,
+        completed_at: operation.completed_at?,
+    })
+}
+
+fn offsets_of(value: &Value) -> HashMap<String, u64> {
+    value
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(key, offset)| Some((key.clone(), offset.as_u64()?)))
+        .collect()
+}
+
+// Checks a history of Kafka-style `send`, `poll`, `commit_offsets` and
+// `list_committed_offsets` operations: every offset of a key holds a single
+// message, offsets grow with real time, polls return messages in order
+// without skipping acknowledged sends, and committed offsets never go back.
+pub fn check(history: &History) -> KafkaReport {
+    let operations = history.operations();
+    let mut report = KafkaReport::default();
+    let mut sends = vec![];
+    let mut polls: HashMap<String, Vec<Poll>> = HashMap::new();
+    let mut commits = vec![];
+    let mut lists = vec![];
+    for operation in &operations {
+        match operation.f.as_str() {
+            "send" => {
+                report.sends += 1;
+                if let Some(send) = send_of(operation).filter(|_| operation.is_ok()) {
+                    report.acknowledged += 1;
+                    sends.push(send);
+                }
+            }
+            "poll" if operation.is_ok() => {
+                report.polls += 1;
+                let requested = offsets_of(&operation.input);
+                let output = operation.output.clone().unwrap_or_default();
+                let Ok(msgs) = serde_json::from_value::<HashMap<String, Vec<(u64, u64)>>>(output)
+                else {
+                    report.problems.push(format!(
+                        "poll by process {} returned malformed messages",
+                        operation.process
+                    ));
+                    continue;
+                };
+
+                for (key, msgs) in msgs {
+                    let from = requested.get(&key).copied().unwrap_or_default();
+                    let in_order = msgs.windows(2).all(|pair| pair[0].0 < pair[1].0);
+                    if !in_order || msgs.first().is_some_and(|&(offset, _)| offset < from) {
+                        report.problems.push(format!(
+                            "poll of {key} from {from} by process {} returned {msgs:?}",
+                            operation.process
+                        ));
+                    }
+
+                    polls.entry(key).or_default().push(Poll {
+                        invoked_at: operation.invoked_at,
+                        from,
+                        msgs: msgs.into_iter().collect(),
+                    });
+                }
+            }
+            "commit_offsets" if operation.is_ok() => {
+                if let Some(completed_at) = operation.completed_at {
+                    commits.push((completed_at, offsets_of(&operation.input)));
+                }
+            }
+            "list_committed_offsets" if operation.is_ok() => {
+                let keys = serde_json::from_value::<Vec<String>>(operation.input.clone())
+                    .unwrap_or_default();
+                let listed = offsets_of(&operation.output.clone().unwrap_or_default());
+                lists.push((operation, keys, listed));
+            }
+            _ => {}
+        }
+    }
+
+    let mut held: HashMap<(&str, u64), u64> = HashMap::new();
+    let mut placed: HashMap<(&str, u64), u64> = HashMap::new();
+    let observed = sends
+        .iter()
+        .map(|send| (send.key.as_str(), send.offset, send.msg))
+        .chain(polls.iter().flat_map(|(key, polls)| {
+            polls.iter().flat_map(move |poll| {
+                poll.msgs
+                    .iter()
+                    .map(move |(&offset, &msg)| (key.as_str(), offset, msg))
+            })
+        }));
+
+    for (key, offset, msg) in observed {
+        match held.insert((key, offset), msg) {
+            Some(other) if other != msg => report.problems.push(format!(
+                "offset {offset} of {key} holds both {other} and {msg}"
+            )),
+            _ => {}
+        }
+
+        match placed.insert((key, msg), offset) {
+            Some(other) if other != offset => report.problems.push(format!(
+                "message {msg} of {key} appears at offsets {other} and {offset}"
+            )),
+            _ => {}
+        }
+    }
+
+    for send in &sends {
+        let later = sends.iter().filter(|other| {
+            other.key == send.key
+                && other.invoked_at > send.completed_at
+                && other.offset <= send.offset
+        });
+        for other in later {
+            report.problems.push(format!(
+                "send of {} to {} got offset {} after an earlier send got {}",
+                other.msg, send.key, other.offset, send.offset
+            ));
+        }
+
+        let skipped = polls.get(&send.key).into_iter().flatten().any(|poll| {
+            let Some((&last, _)) = poll.msgs.last_key_value() else {
+                return false;
+            };
+
+            poll.invoked_at > send.completed_at
+                && (poll.from..=last).contains(&send.offset)
+                && !poll.msgs.contains_key(&send.offset)
+        });
+
+        if skipped {
+            report.lost += 1;
+            report.problems.push(format!(
+                "acknowledged send of {} to {} at offset {} was skipped by a poll",
+                send.msg, send.key, send.offset
+            ));
+        }
+    }
+
+    for (list, keys, listed) in &lists {
+        for key in keys {
+            let committed = commits
+                .iter()
+                .filter(|(completed_at, _)| *completed_at < list.invoked_at)
+                .filter_map(|(_, offsets)| offsets.get(key))
+                .max();
+
+            let Some(&committed) = committed else {
+                continue;
+            };
+
+            if listed.get(key).is_none_or(|&listed| listed < committed) {
+                report.problems.push(format!(
+                    "process {} listed {:?} for {key} after {committed} was committed",
+                    list.process,
+                    listed.get(key)
+                ));
+            }
+        }
+    }
+
+    report
+}
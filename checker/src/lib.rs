@@ -0,0 +1,10 @@
+pub mod broadcast;
+pub mod counter;
+pub mod edn;
+pub mod error;
+pub mod history;
+pub mod kafka;
+pub mod linearizability;
+pub mod results;
+pub mod stats;
+pub mod txn;
@@ -0,0 +1,523 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Debug,
+    hash::Hash,
+};
+
+use serde_json::Value;
+
+use crate::history::{EventKind, History, Operation};
+
+pub trait Model {
+    type State: Clone + Eq + Hash + Debug;
+    type Op: Clone + Debug;
+
+    fn init(&self) -> Self::State;
+
+    // Returns the key the operation belongs to and its typed form, or `None`
+    // if the operation cannot affect the check (failed writes, unfinished
+    // reads, other workloads' operations).
+    fn op_of(&self, operation: &Operation) -> Option<(String, Self::Op)>;
+
+    fn step(&self, state: &Self::State, op: &Self::Op) -> Option<Self::State>;
+
+    // Whether `op` observes a value that `other` produced. Shrinking never
+    // drops an operation something else still depends on, so a reported
+    // sub-history only reads values it also writes.
+    fn depends_on(&self, _op: &Self::Op, _other: &Self::Op) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RegisterModel;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegisterOp {
+    Read(Option<i64>),
+    Write(i64),
+    Cas(i64, i64),
+}
+
+// Maelstrom's lin-kv workload records every value as an independent
+// `[key, value]` tuple, while single-register histories use a bare value.
+fn split_key(value: &Value) -> (String, &Value) {
+    match value.as_array().map(Vec::as_slice) {
+        Some([key, value]) => (key.to_string(), value),
+        _ => (String::new(), value),
+    }
+}
+
+impl Model for RegisterModel {
+    type State = Option<i64>;
+    type Op = RegisterOp;
+
+    fn init(&self) -> Self::State {
+        None
+    }
+
+    fn op_of(&self, operation: &Operation) -> Option<(String, Self::Op)> {
+        if operation.outcome == EventKind::Fail {
+            return None;
+        }
+
+        let (key, input) = split_key(&operation.input);
+        let op = match operation.f.as_str() {
+            "read" => {
+                if !operation.is_ok() {
+                    return None;
+                }
+
+                let (_, output) = split_key(operation.output.as_ref()?);
+                RegisterOp::Read(output.as_i64())
+            }
+            "write" => RegisterOp::Write(input.as_i64()?),
+            "cas" => match input.as_array().map(Vec::as_slice) {
+                Some([from, to]) => RegisterOp::Cas(from.as_i64()?, to.as_i64()?),
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        Some((key, op))
+    }
+
+    fn step(&self, state: &Self::State, op: &Self::Op) -> Option<Self::State> {
+        match *op {
+            RegisterOp::Read(value) => (*state == value).then_some(*state),
+            RegisterOp::Write(value) => Some(Some(value)),
+            RegisterOp::Cas(from, to) => (*state == Some(from)).then_some(Some(to)),
+        }
+    }
+
+    fn depends_on(&self, op: &Self::Op, other: &Self::Op) -> bool {
+        let observed = match *op {
+            RegisterOp::Read(Some(value)) | RegisterOp::Cas(value, _) => value,
+            _ => return false,
+        };
+
+        matches!(*other, RegisterOp::Write(value) | RegisterOp::Cas(_, value) if value == observed)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CounterModel;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CounterOp {
+    Add(i64),
+    Read(i64),
+}
+
+impl Model for CounterModel {
+    type State = i64;
+    type Op = CounterOp;
+
+    fn init(&self) -> Self::State {
+        0
+    }
+
+    fn op_of(&self, operation: &Operation) -> Option<(String, Self::Op)> {
+        if operation.outcome == EventKind::Fail {
+            return None;
+        }
+
+        let op = match operation.f.as_str() {
+            "add" => CounterOp::Add(operation.input.as_i64()?),
+            "read" if operation.is_ok() => CounterOp::Read(operation.output.as_ref()?.as_i64()?),
+            _ => return None,
+        };
+
+        Some((String::new(), op))
+    }
+
+    fn step(&self, state: &Self::State, op: &Self::Op) -> Option<Self::State> {
+        match *op {
+            CounterOp::Add(delta) => Some(state + delta),
+            CounterOp::Read(value) => (*state == value).then_some(*state),
+        }
+    }
+
+    fn depends_on(&self, op: &Self::Op, other: &Self::Op) -> bool {
+        matches!((op, other), (CounterOp::Read(_), CounterOp::Add(_)))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Verdict {
+    Linearizable,
+    Violation {
+        key: String,
+        operations: Vec<Operation>,
+    },
+}
+
+impl Verdict {
+    pub fn is_linearizable(&self) -> bool {
+        matches!(self, Self::Linearizable)
+    }
+
+    pub fn sub_history(&self) -> History {
+        match self {
+            Self::Linearizable => History::default(),
+            Self::Violation { operations, .. } => History::from_operations(operations),
+        }
+    }
+}
+
+pub fn check<M: Model>(model: &M, history: &History) -> Verdict {
+    let mut keys: BTreeMap<String, Vec<(Operation, M::Op)>> = BTreeMap::new();
+    for operation in history.operations() {
+        if let Some((key, op)) = model.op_of(&operation) {
+            keys.entry(key).or_default().push((operation, op));
+        }
+    }
+
+    for (key, operations) in keys {
+        if !is_linearizable(model, &operations) {
+            let operations = shrink(model, operations)
+                .into_iter()
+                .map(|(operation, _)| operation)
+                .collect();
+
+            return Verdict::Violation { key, operations };
+        }
+    }
+
+    Verdict::Linearizable
+}
+
+// Narrows a failing history down to a 1-minimal one: first the shortest
+// failing prefix by invocation time, then every operation that nothing else
+// depends on and whose removal keeps the history non-linearizable is dropped.
+fn shrink<M: Model>(model: &M, mut operations: Vec<(Operation, M::Op)>) -> Vec<(Operation, M::Op)> {
+    operations.sort_by_key(|(operation, _)| operation.invoked_at);
+
+    let mut length = 1;
+    while length < operations.len() && is_linearizable(model, &operations[..length]) {
+        length += 1;
+    }
+
+    operations.truncate(length);
+
+    let mut index = 0;
+    while index < operations.len() {
+        let (_, op) = &operations[index];
+        let needed = operations
+            .iter()
+            .enumerate()
+            .any(|(other, (_, other_op))| other != index && model.depends_on(other_op, op));
+
+        if needed {
+            index += 1;
+            continue;
+        }
+
+        let candidate = operations
+            .iter()
+            .enumerate()
+            .filter(|&(other, _)| other != index)
+            .map(|(_, entry)| entry.clone())
+            .collect::<Vec<_>>();
+
+        if is_linearizable(model, &candidate) {
+            index += 1;
+        } else {
+            operations = candidate;
+        }
+    }
+
+    operations
+}
+
+const NIL: usize = usize::MAX;
+
+#[derive(Debug, Clone, Copy)]
+enum EntryKind {
+    Call,
+    Return,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    kind: EntryKind,
+    operation: usize,
+    matching: usize,
+    prev: usize,
+    next: usize,
+}
+
+struct Entries {
+    entries: Vec<Entry>,
+}
+
+impl Entries {
+    const HEAD: usize = 0;
+
+    // Lays the calls and returns out on a doubly-linked list ordered by time,
+    // with calls before returns on ties. Operations that never completed
+    // return after everything else.
+    fn new<O>(operations: &[(Operation, O)]) -> Self {
+        let mut events = operations
+            .iter()
+            .enumerate()
+            .flat_map(|(index, (operation, _))| {
+                let completed_at = operation.completed_at.unwrap_or(u64::MAX);
+                [(operation.invoked_at, 0, index), (completed_at, 1, index)]
+            })
+            .collect::<Vec<_>>();
+        events.sort();
+
+        let mut entries = vec![Entry {
+            kind: EntryKind::Call,
+            operation: NIL,
+            matching: NIL,
+            prev: NIL,
+            next: NIL,
+        }];
+
+        let mut calls = vec![NIL; operations.len()];
+        for (_, order, operation) in events {
+            let index = entries.len();
+            let kind = if order == 0 {
+                calls[operation] = index;
+                EntryKind::Call
+            } else {
+                let call = calls[operation];
+                entries[call].matching = index;
+                EntryKind::Return
+            };
+
+            entries[index - 1].next = index;
+            entries.push(Entry {
+                kind,
+                operation,
+                matching: calls[operation],
+                prev: index - 1,
+                next: NIL,
+            });
+        }
+
+        Self { entries }
+    }
+
+    fn first(&self) -> usize {
+        self.entries[Self::HEAD].next
+    }
+
+    fn unlink(&mut self, index: usize) {
+        let Entry { prev, next, .. } = self.entries[index];
+        self.entries[prev].next = next;
+        if next != NIL {
+            self.entries[next].prev = prev;
+        }
+    }
+
+    fn relink(&mut self, index: usize) {
+        let Entry { prev, next, .. } = self.entries[index];
+        self.entries[prev].next = index;
+        if next != NIL {
+            self.entries[next].prev = index;
+        }
+    }
+
+    fn lift(&mut self, call: usize) {
+        self.unlink(call);
+        self.unlink(self.entries[call].matching);
+    }
+
+    fn unlift(&mut self, call: usize) {
+        self.relink(self.entries[call].matching);
+        self.relink(call);
+    }
+}
+
+fn is_linearizable<M: Model>(model: &M, operations: &[(Operation, M::Op)]) -> bool {
+    let mut entries = Entries::new(operations);
+    let mut linearized = vec![0u64; operations.len().div_ceil(64)];
+    let mut cache = HashSet::new();
+    let mut calls = vec![];
+    let mut state = model.init();
+
+    let mut current = entries.first();
+    while entries.first() != NIL {
+        let entry = entries.entries[current];
+        match entry.kind {
+            EntryKind::Call => {
+                let (operation, op) = &operations[entry.operation];
+                // An indeterminate operation may also never have happened.
+                let next_state = model
+                    .step(&state, op)
+                    .or_else(|| operation.completed_at.is_none().then(|| state.clone()));
+
+                let Some(next_state) = next_state else {
+                    current = entry.next;
+                    continue;
+                };
+
+                let (word, bit) = (entry.operation / 64, entry.operation % 64);
+                linearized[word] |= 1 << bit;
+                if cache.insert((linearized.clone(), next_state.clone())) {
+                    calls.push((current, state));
+                    state = next_state;
+                    entries.lift(current);
+                    current = entries.first();
+                } else {
+                    linearized[word] &= !(1 << bit);
+                    current = entry.next;
+                }
+            }
+            EntryKind::Return => {
+                let Some((call, previous_state)) = calls.pop() else {
+                    return false;
+                };
+
+                let operation = entries.entries[call].operation;
+                linearized[operation / 64] &= !(1 << (operation % 64));
+                state = previous_state;
+                entries.unlift(call);
+                current = entries.entries[call].next;
+            }
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    // One operation per process, so every invocation pairs with its own
+    // completion.
+    fn operation(
+        f: &str,
+        input: Value,
+        output: Value,
+        invoked_at: u64,
+        completed_at: u64,
+    ) -> Operation {
+        Operation {
+            process: invoked_at as usize,
+            f: f.to_string(),
+            node: None,
+            input,
+            output: Some(output),
+            outcome: EventKind::Ok,
+            invoked_at,
+            completed_at: Some(completed_at),
+        }
+    }
+
+    fn history(operations: &[Operation]) -> History {
+        History::from_operations(operations)
+    }
+
+    #[test]
+    fn accepts_reads_that_overlap_a_write() {
+        // The read of 1 overlaps the write of 2, so it may take effect either
+        // side of it.
+        let history = history(&[
+            operation("write", json!(1), Value::Null, 0, 10),
+            operation("write", json!(2), Value::Null, 20, 40),
+            operation("read", Value::Null, json!(1), 25, 30),
+            operation("read", Value::Null, json!(2), 50, 60),
+        ]);
+
+        assert!(check(&RegisterModel, &history).is_linearizable());
+    }
+
+    #[test]
+    fn rejects_a_stale_read() {
+        let history = history(&[
+            operation("write", json!(1), Value::Null, 0, 10),
+            operation("write", json!(2), Value::Null, 20, 30),
+            operation("read", Value::Null, json!(1), 40, 50),
+        ]);
+
+        assert!(!check(&RegisterModel, &history).is_linearizable());
+    }
+
+    #[test]
+    fn indeterminate_writes_may_or_may_not_happen() {
+        let mut lost = operation("write", json!(2), Value::Null, 20, 30);
+        lost.outcome = EventKind::Info;
+        lost.output = None;
+        lost.completed_at = None;
+
+        let history = history(&[
+            operation("write", json!(1), Value::Null, 0, 10),
+            lost,
+            operation("read", Value::Null, json!(1), 40, 50),
+            operation("read", Value::Null, json!(2), 60, 70),
+        ]);
+
+        // The write never completed, so it may land between the two reads.
+        assert!(check(&RegisterModel, &history).is_linearizable());
+    }
+
+    #[test]
+    fn keys_are_checked_independently() {
+        let history = history(&[
+            operation("write", json!([1, 1]), Value::Null, 0, 10),
+            operation("write", json!([2, 5]), Value::Null, 20, 30),
+            operation("read", json!([1, null]), json!([1, 1]), 40, 50),
+            operation("read", json!([2, null]), json!([2, 1]), 60, 70),
+        ]);
+
+        match check(&RegisterModel, &history) {
+            Verdict::Violation { key, .. } => assert_eq!(key, "2"),
+            Verdict::Linearizable => panic!("expected key 2 to fail"),
+        }
+    }
+
+    #[test]
+    fn shrinks_to_the_operations_behind_the_violation() {
+        let history = history(&[
+            operation("write", json!(1), Value::Null, 0, 10),
+            operation("read", Value::Null, json!(1), 15, 18),
+            operation("write", json!(2), Value::Null, 20, 30),
+            operation("read", Value::Null, json!(2), 32, 35),
+            operation("read", Value::Null, json!(1), 40, 50),
+            operation("write", json!(3), Value::Null, 60, 70),
+            operation("read", Value::Null, json!(3), 80, 90),
+        ]);
+
+        let verdict = check(&RegisterModel, &history);
+        let shrunk = verdict
+            .sub_history()
+            .operations()
+            .into_iter()
+            .map(|operation| (operation.f, operation.input, operation.output))
+            .collect::<Vec<_>>();
+
+        // Everything after the stale read is cut, and of what came before
+        // only the two writes it is ordered against are kept.
+        assert_eq!(
+            shrunk,
+            vec![
+                ("write".to_string(), json!(1), Some(Value::Null)),
+                ("write".to_string(), json!(2), Some(Value::Null)),
+                ("read".to_string(), Value::Null, Some(json!(1))),
+            ]
+        );
+    }
+
+    #[test]
+    fn counter_reads_must_match_the_adds_before_them() {
+        let adds = [
+            operation("add", json!(2), Value::Null, 0, 10),
+            operation("add", json!(3), Value::Null, 20, 30),
+        ];
+
+        let mut ok = adds.to_vec();
+        ok.push(operation("read", Value::Null, json!(5), 40, 50));
+        assert!(check(&CounterModel, &history(&ok)).is_linearizable());
+
+        let mut short = adds.to_vec();
+        short.push(operation("read", Value::Null, json!(2), 40, 50));
+        assert!(!check(&CounterModel, &history(&short)).is_linearizable());
+    }
+}
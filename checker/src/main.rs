@@ -0,0 +1,202 @@
+use std::{fs::File, io::BufReader, process::ExitCode, time::Duration};
+
+use anyhow::{anyhow, bail, Context};
+
+use checker::{
+    broadcast::{self, Thresholds},
+    counter,
+    history::{History, MessageLog},
+    kafka,
+    linearizability::{self, CounterModel, RegisterModel},
+    results::MaelstromResults,
+    txn::{self, ConsistencyModel},
+};
+
+const USAGE: &str = "usage:
+  checker broadcast <history.jsonl> <messages.jsonl> [THRESHOLDS]
+  checker results [results.edn] [THRESHOLDS]
+  checker linearizable <history.jsonl> [--model register|counter]
+  checker counter <history.jsonl> <messages.jsonl> [THRESHOLDS]
+  checker kafka <history.jsonl>
+  checker txn <history.jsonl> [--model read-uncommitted|read-committed]
+THRESHOLDS: [--challenge 3d|3e] [--max-lost N] [--max-stale-reads N] [--max-msgs-per-op N] \
+[--max-median-latency MS] [--max-latency MS]";
+
+fn open(path: &str) -> anyhow::Result<BufReader<File>> {
+    let file = File::open(path).with_context(|| format!("failed to open {path}"))?;
+    Ok(BufReader::new(file))
+}
+
+fn flag_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> anyhow::Result<T> {
+    value
+        .ok_or_else(|| anyhow!("{flag} expects a value"))?
+        .parse()
+        .map_err(|_| anyhow!("{flag} expects a number"))
+}
+
+fn parse_thresholds(mut args: impl Iterator<Item = String>) -> anyhow::Result<Thresholds> {
+    let mut thresholds = Thresholds::default();
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--challenge" => {
+                thresholds = match args.next().as_deref() {
+                    Some("3d") => Thresholds::challenge_3d(),
+                    Some("3e") => Thresholds::challenge_3e(),
+                    _ => bail!("--challenge expects 3d or 3e"),
+                }
+            }
+            "--max-lost" => thresholds.max_lost = flag_value(&flag, args.next())?,
+            "--max-stale-reads" => {
+                thresholds.max_stale_reads = Some(flag_value(&flag, args.next())?)
+            }
+            "--max-msgs-per-op" => {
+                thresholds.max_msgs_per_op = Some(flag_value(&flag, args.next())?)
+            }
+            "--max-median-latency" => {
+                let millis = flag_value(&flag, args.next())?;
+                thresholds.max_median_latency = Some(Duration::from_millis(millis));
+            }
+            "--max-latency" => {
+                let millis = flag_value(&flag, args.next())?;
+                thresholds.max_latency = Some(Duration::from_millis(millis));
+            }
+            _ => bail!("unknown flag {flag}\n{USAGE}"),
+        }
+    }
+
+    Ok(thresholds)
+}
+
+fn report_violations(violations: &[String]) -> bool {
+    for violation in violations {
+        println!("FAIL: {violation}");
+    }
+
+    violations.is_empty()
+}
+
+fn check_broadcast(mut args: impl Iterator<Item = String>) -> anyhow::Result<bool> {
+    let history_path = args.next().ok_or_else(|| anyhow!(USAGE))?;
+    let log_path = args.next().ok_or_else(|| anyhow!(USAGE))?;
+    let thresholds = parse_thresholds(args)?;
+
+    let history = History::read_from(open(&history_path)?)?;
+    let log = MessageLog::read_from(open(&log_path)?)?;
+    let report = broadcast::check(&history, &log);
+    println!("{report}");
+
+    Ok(report_violations(&report.violations(&thresholds)))
+}
+
+fn check_results(mut args: impl Iterator<Item = String>) -> anyhow::Result<bool> {
+    let results_path = args
+        .next()
+        .unwrap_or_else(|| String::from("store/latest/results.edn"));
+    let thresholds = parse_thresholds(args)?;
+
+    let input = std::fs::read_to_string(&results_path)
+        .with_context(|| format!("failed to read {results_path}"))?;
+    let results = MaelstromResults::parse(&input)?;
+    println!("{results}");
+
+    Ok(report_violations(&results.violations(&thresholds)))
+}
+
+fn check_linearizable(mut args: impl Iterator<Item = String>) -> anyhow::Result<bool> {
+    let history_path = args.next().ok_or_else(|| anyhow!(USAGE))?;
+
+    let mut model = String::from("register");
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--model" => {
+                model = args
+                    .next()
+                    .ok_or_else(|| anyhow!("--model expects a value"))?
+            }
+            _ => bail!("unknown flag {flag}\n{USAGE}"),
+        }
+    }
+
+    let history = History::read_from(open(&history_path)?)?;
+    let verdict = match model.as_str() {
+        "register" => linearizability::check(&RegisterModel, &history),
+        "counter" => linearizability::check(&CounterModel, &history),
+        _ => bail!("--model expects register or counter"),
+    };
+
+    match &verdict {
+        linearizability::Verdict::Linearizable => println!("linearizable"),
+        linearizability::Verdict::Violation { key, .. } => {
+            println!("FAIL: history of key {key} is not linearizable; minimal sub-history:");
+            verdict
+                .sub_history()
+                .write_to(&mut std::io::stdout().lock())?;
+        }
+    }
+
+    Ok(verdict.is_linearizable())
+}
+
+fn check_counter(mut args: impl Iterator<Item = String>) -> anyhow::Result<bool> {
+    let history_path = args.next().ok_or_else(|| anyhow!(USAGE))?;
+    let log_path = args.next().ok_or_else(|| anyhow!(USAGE))?;
+    let thresholds = parse_thresholds(args)?;
+    let history = History::read_from(open(&history_path)?)?;
+    let log = MessageLog::read_from(open(&log_path)?)?;
+    let report = counter::check(&history, &log);
+    println!("{report}");
+
+    Ok(report_violations(&report.violations(&thresholds)))
+}
+
+fn check_kafka(mut args: impl Iterator<Item = String>) -> anyhow::Result<bool> {
+    let history_path = args.next().ok_or_else(|| anyhow!(USAGE))?;
+    let history = History::read_from(open(&history_path)?)?;
+    let report = kafka::check(&history);
+    println!("{report}");
+
+    Ok(report_violations(&report.problems))
+}
+
+fn check_txn(mut args: impl Iterator<Item = String>) -> anyhow::Result<bool> {
+    let history_path = args.next().ok_or_else(|| anyhow!(USAGE))?;
+
+    let mut model = ConsistencyModel::ReadCommitted;
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--model" => {
+                model = args
+                    .next()
+                    .as_deref()
+                    .and_then(ConsistencyModel::parse)
+                    .ok_or_else(|| anyhow!("--model expects read-uncommitted or read-committed"))?
+            }
+            _ => bail!("unknown flag {flag}\n{USAGE}"),
+        }
+    }
+
+    let history = History::read_from(open(&history_path)?)?;
+    let report = txn::check(&history);
+    println!("{report}");
+
+    Ok(report_violations(&report.violations(model)))
+}
+
+pub fn main() -> anyhow::Result<ExitCode> {
+    let mut args = std::env::args().skip(1);
+    let passed = match args.next().as_deref() {
+        Some("broadcast") => check_broadcast(args)?,
+        Some("results") => check_results(args)?,
+        Some("linearizable") => check_linearizable(args)?,
+        Some("counter") => check_counter(args)?,
+        Some("kafka") => check_kafka(args)?,
+        Some("txn") => check_txn(args)?,
+        _ => bail!(USAGE),
+    };
+
+    Ok(if passed {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
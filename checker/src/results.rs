@@ -0,0 +1,225 @@
+use std::{fmt::Display, time::Duration};
+
+use crate::{
+    broadcast::Thresholds,
+    edn::{self, Edn},
+    error::CheckerError,
+};
+
+// The parts of Maelstrom's store/latest/results.edn we hold runs to.
+#[derive(Debug, Clone, Default)]
+pub struct MaelstromResults {
+    pub valid: Option<bool>,
+    pub msgs_per_op: Option<f64>,
+    pub lost_count: Option<usize>,
+    pub stale_count: Option<usize>,
+    pub stable_latencies: Vec<(f64, Duration)>,
+}
+
+impl MaelstromResults {
+    pub fn parse(input: &str) -> Result<Self, CheckerError> {
+        let results = edn::parse(input)?;
+        let count = |path: &[&str]| {
+            results
+                .get_in(path)
+                .and_then(Edn::as_f64)
+                .map(|count| count as usize)
+        };
+
+        let mut stable_latencies = results
+            .get_in(&["workload", "stable-latencies"])
+            .map(Edn::entries)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|(quantile, millis)| {
+                let millis = millis.as_f64()?.max(0.0);
+                Some((quantile.as_f64()?, Duration::from_secs_f64(millis / 1000.0)))
+            })
+            .collect::<Vec<_>>();
+        stable_latencies.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+        Ok(Self {
+            valid: results.get("valid?").and_then(Edn::as_bool),
+            msgs_per_op: results
+                .get_in(&["net", "servers", "msgs-per-op"])
+                .and_then(Edn::as_f64),
+            lost_count: count(&["workload", "lost-count"]),
+            stale_count: count(&["workload", "stale-count"]),
+            stable_latencies,
+        })
+    }
+
+    pub fn latency_at(&self, quantile: f64) -> Option<Duration> {
+        self.stable_latencies
+            .iter()
+            .find(|(q, _)| (q - quantile).abs() < f64::EPSILON)
+            .map(|&(_, latency)| latency)
+    }
+
+    pub fn violations(&self, thresholds: &Thresholds) -> Vec<String> {
+        let mut violations = vec![];
+        match self.valid {
+            Some(true) => {}
+            Some(false) => violations.push(String::from("maelstrom reported :valid? false")),
+            None => violations.push(String::from("maelstrom could not determine validity")),
+        }
+
+        if let Some(lost) = self.lost_count.filter(|&lost| lost > thresholds.max_lost) {
+            violations.push(format!(
+                "{lost} acknowledged broadcasts were lost (allowed {})",
+                thresholds.max_lost
+            ));
+        }
+
+        if let (Some(max), Some(stale)) = (thresholds.max_stale_reads, self.stale_count) {
+            if stale > max {
+                violations.push(format!("{stale} stale reads (allowed {max})"));
+            }
+        }
+
+        if let Some(max) = thresholds.max_msgs_per_op {
+            match self.msgs_per_op {
+                Some(msgs_per_op) if msgs_per_op > max => violations.push(format!(
+                    "{msgs_per_op:.2} server msgs-per-op (allowed {max:.2})"
+                )),
+                None => violations.push(String::from("results have no server msgs-per-op")),
+                _ => {}
+            }
+        }
+
+        let budgets = [
+            ("median", 0.5, thresholds.max_median_latency),
+            ("max", 1.0, thresholds.max_latency),
+        ];
+
+        for (label, quantile, budget) in budgets {
+            let Some(max) = budget else {
+                continue;
+            };
+
+            match self.latency_at(quantile) {
+                Some(latency) if latency > max => violations.push(format!(
+                    "{label} stable latency {}ms (allowed {}ms)",
+                    latency.as_millis(),
+                    max.as_millis()
+                )),
+                None => violations.push(format!("results have no {label} stable latency")),
+                _ => {}
+            }
+        }
+
+        violations
+    }
+}
+
+impl Display for MaelstromResults {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let or_unknown = |value: Option<String>| value.unwrap_or_else(|| String::from("?"));
+        writeln!(
+            f,
+            "valid:            {}",
+            or_unknown(self.valid.map(|v| v.to_string()))
+        )?;
+        writeln!(
+            f,
+            "msgs-per-op:      {}",
+            or_unknown(self.msgs_per_op.map(|m| format!("{m:.2}")))
+        )?;
+        writeln!(
+            f,
+            "lost:             {}",
+            or_unknown(self.lost_count.map(|l| l.to_string()))
+        )?;
+        writeln!(
+            f,
+            "stale reads:      {}",
+            or_unknown(self.stale_count.map(|s| s.to_string()))
+        )?;
+
+        let latencies = self
+            .stable_latencies
+            .iter()
+            .map(|(quantile, latency)| format!("{quantile}: {}ms", latency.as_millis()))
+            .collect::<Vec<_>>();
+        write!(f, "stable latencies: {}", latencies.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Trimmed from a challenge 3d run.
+    const RESULTS: &str = r#"{:perf {:latency-graph {:valid? true},
+        :rate-graph {:valid? true},
+        :valid? true},
+ :timeline {:valid? true},
+ :exceptions {:valid? true},
+ :stats {:valid? true,
+         :count 1956,
+         :ok-count 1956,
+         :fail-count 0,
+         :info-count 0,
+         :by-f {:broadcast {:valid? true, :count 978, :ok-count 978},
+                :read {:valid? true, :count 978, :ok-count 978}}},
+ :availability {:valid? true, :ok-fraction 1.0},
+ :net {:all {:send-count 48390,
+             :recv-count 48390,
+             :msg-count 48390,
+             :msgs-per-op 24.739264},
+       :clients {:send-count 4012, :recv-count 4012, :msg-count 4012},
+       :servers {:send-count 44378,
+                 :recv-count 44378,
+                 :msg-count 44378,
+                 :msgs-per-op 22.688137},
+       :valid? true},
+ :workload {:worst-stale (),
+            :duplicated-count 0,
+            :valid? true,
+            :lost-count 0,
+            :lost #{},
+            :stable-count 978,
+            :stale-count 0,
+            :stale #{},
+            :never-read-count 0,
+            :stable-latencies {0 0,
+                               0.5 371,
+                               0.95 468,
+                               0.99 497,
+                               1 533},
+            :attempt-count 978,
+            :never-read #{},
+            :duplicated {}},
+ :valid? true}
+"#;
+
+    #[test]
+    fn reads_a_results_file() {
+        let results = MaelstromResults::parse(RESULTS).unwrap();
+        assert_eq!(results.valid, Some(true));
+        assert_eq!(results.msgs_per_op, Some(22.688137));
+        assert_eq!(results.lost_count, Some(0));
+        assert_eq!(results.stale_count, Some(0));
+        assert_eq!(results.latency_at(0.5), Some(Duration::from_millis(371)));
+        assert_eq!(results.latency_at(1.0), Some(Duration::from_millis(533)));
+        assert!(results.violations(&Thresholds::challenge_3d()).is_empty());
+    }
+
+    #[test]
+    fn reports_what_exceeds_the_thresholds() {
+        let results = MaelstromResults::parse(
+            &RESULTS
+                .replace("0.5 371", "0.5 412")
+                .replace(":lost-count 0", ":lost-count 2"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            results.violations(&Thresholds::challenge_3d()),
+            vec![
+                "2 acknowledged broadcasts were lost (allowed 0)".to_string(),
+                "median stable latency 412ms (allowed 400ms)".to_string(),
+            ]
+        );
+    }
+}
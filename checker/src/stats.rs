@@ -0,0 +1,58 @@
+use std::{fmt::Display, time::Duration};
+
+#[derive(Debug, Clone, Default)]
+pub struct Percentiles {
+    sorted: Vec<Duration>,
+}
+
+impl Percentiles {
+    pub fn new(mut samples: Vec<Duration>) -> Self {
+        samples.sort_unstable();
+        Self { sorted: samples }
+    }
+
+    pub fn len(&self) -> usize {
+        self.sorted.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sorted.is_empty()
+    }
+
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        if self.sorted.is_empty() {
+            return None;
+        }
+
+        let index = ((self.sorted.len() - 1) as f64 * q.clamp(0.0, 1.0)).round() as usize;
+        Some(self.sorted[index])
+    }
+
+    pub fn median(&self) -> Option<Duration> {
+        self.quantile(0.5)
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        self.sorted.last().copied()
+    }
+}
+
+impl Display for Percentiles {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.sorted.is_empty() {
+            return write!(f, "(no samples)");
+        }
+
+        let quantiles = [0.0, 0.5, 0.95, 0.99, 1.0]
+            .iter()
+            .map(|&q| {
+                format!(
+                    "{q}: {}ms",
+                    self.quantile(q).unwrap_or_default().as_millis()
+                )
+            })
+            .collect::<Vec<_>>();
+
+        write!(f, "{}", quantiles.join(", "))
+    }
+}
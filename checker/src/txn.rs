@@ -0,0 +1,681 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use maelstrom::txn::MicroOp;
+use serde_json::Value;
+
+use crate::history::{EventKind, History};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnomalyKind {
+    // A cycle of write-write dependencies in the inferred version order; see
+    // `check`.
+    G0,
+    // A committed transaction read a value written by an aborted one.
+    G1a,
+    // A committed transaction read a value its writer later overwrote.
+    G1b,
+    // A cycle of write-write and write-read dependencies.
+    G1c,
+    // A committed transaction read a value nobody wrote.
+    GarbageRead,
+}
+
+impl Display for AnomalyKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::G0 => write!(f, "G0"),
+            Self::G1a => write!(f, "G1a"),
+            Self::G1b => write!(f, "G1b"),
+            Self::G1c => write!(f, "G1c"),
+            Self::GarbageRead => write!(f, "garbage read"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsistencyModel {
+    ReadUncommitted,
+    ReadCommitted,
+}
+
+impl ConsistencyModel {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "read-uncommitted" => Some(Self::ReadUncommitted),
+            "read-committed" => Some(Self::ReadCommitted),
+            _ => None,
+        }
+    }
+
+    pub fn prohibits(&self, kind: AnomalyKind) -> bool {
+        match self {
+            Self::ReadUncommitted => matches!(kind, AnomalyKind::G0 | AnomalyKind::GarbageRead),
+            Self::ReadCommitted => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dependency {
+    WriteWrite,
+    WriteRead,
+}
+
+impl Display for Dependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::WriteWrite => write!(f, "ww"),
+            Self::WriteRead => write!(f, "wr"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    pub index: usize,
+    pub process: usize,
+    pub outcome: EventKind,
+    pub ops: Vec<MicroOp>,
+}
+
+impl Display for Transaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ops = serde_json::to_string(&self.ops).map_err(|_| std::fmt::Error)?;
+        write!(
+            f,
+            "T{} (process {}, {:?}): {ops}",
+            self.index, self.process, self.outcome
+        )
+    }
+}
+
+// An anomaly names the transactions involved; for cycles they are listed in
+// order, each depending on the previous one through the matching edge.
+#[derive(Debug, Clone)]
+pub struct Anomaly {
+    pub kind: AnomalyKind,
+    pub description: String,
+    pub transactions: Vec<Transaction>,
+    pub edges: Vec<Dependency>,
+}
+
+impl Display for Anomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.kind, self.description)?;
+        for transaction in &self.transactions {
+            write!(f, "\n  {transaction}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TxnReport {
+    pub transactions: usize,
+    pub committed: usize,
+    pub aborted: usize,
+    pub indeterminate: usize,
+    pub dependencies: usize,
+    // Keys whose writes the history does not put in one order, so a G0 cycle
+    // through them could go unseen.
+    pub unordered_keys: Vec<usize>,
+    pub anomalies: Vec<Anomaly>,
+}
+
+impl TxnReport {
+    pub fn violations(&self, model: ConsistencyModel) -> Vec<String> {
+        self.anomalies
+            .iter()
+            .filter(|anomaly| model.prohibits(anomaly.kind))
+            .map(|anomaly| anomaly.to_string())
+            .collect()
+    }
+}
+
+impl Display for TxnReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "transactions:  {}", self.transactions)?;
+        writeln!(f, "committed:     {}", self.committed)?;
+        writeln!(f, "aborted:       {}", self.aborted)?;
+        writeln!(f, "indeterminate: {}", self.indeterminate)?;
+        writeln!(f, "dependencies:  {}", self.dependencies)?;
+        match self.unordered_keys.len() {
+            0 => writeln!(f, "g0:            checked")?,
+            keys => writeln!(
+                f,
+                "g0:            inconclusive, {keys} keys have writes in no inferable order"
+            )?,
+        }
+
+        write!(f, "anomalies:     {}", self.anomalies.len())
+    }
+}
+
+// Where a value came from: the transaction that wrote it, and whether it was
+// that transaction's last write to the key.
+struct Write {
+    writer: usize,
+    last: bool,
+}
+
+fn txn_of(value: &Value) -> Vec<MicroOp> {
+    serde_json::from_value(value.clone()).unwrap_or_default()
+}
+
+// Reads that observe another transaction's state: those of keys the
+// transaction has not written yet.
+fn external_reads(ops: &[MicroOp]) -> Vec<(usize, Option<usize>)> {
+    let mut written = HashSet::new();
+    let mut reads = vec![];
+    for op in ops {
+        match *op {
+            MicroOp::Read { key, value } if !written.contains(&key) => reads.push((key, value)),
+            MicroOp::Read { .. } => {}
+            MicroOp::Write { key, .. } => {
+                written.insert(key);
+            }
+        }
+    }
+
+    reads
+}
+
+type Graph = HashMap<usize, Vec<(usize, Dependency)>>;
+
+// Whether the write-write edges on one key put all of its writers in a single
+// order, which they do when there is never a choice of which writer comes
+// next. A cycle also leaves the order undecided, but is reported as G0.
+fn is_ordered(writers: &HashSet<usize>, edges: &[(usize, usize)]) -> bool {
+    let mut in_degree = writers
+        .iter()
+        .map(|&writer| (writer, 0))
+        .collect::<HashMap<_, _>>();
+    let mut successors: HashMap<usize, HashSet<usize>> = HashMap::new();
+    for &(from, to) in edges {
+        if successors.entry(from).or_default().insert(to) {
+            *in_degree.entry(to).or_default() += 1;
+        }
+    }
+
+    let mut ready = in_degree
+        .iter()
+        .filter(|(_, degree)| **degree == 0)
+        .map(|(&writer, _)| writer)
+        .collect::<Vec<_>>();
+    while let Some(writer) = ready.pop() {
+        if !ready.is_empty() {
+            return false;
+        }
+
+        for next in successors.get(&writer).into_iter().flatten() {
+            let degree = in_degree.get_mut(next).expect("every writer has a degree");
+            *degree -= 1;
+            if *degree == 0 {
+                ready.push(*next);
+            }
+        }
+    }
+
+    true
+}
+
+// Finds cycles among the edges `allowed` keeps. Peeling off transactions
+// without incoming edges leaves only those on or behind a cycle, each with a
+// remaining predecessor, so walking predecessors from any of them must loop.
+fn cycles(
+    predecessors: &Graph,
+    count: usize,
+    allowed: impl Fn(Dependency) -> bool,
+) -> Vec<Vec<(usize, Dependency)>> {
+    let edges = |node: usize| {
+        predecessors
+            .get(&node)
+            .into_iter()
+            .flatten()
+            .filter(|(_, dependency)| allowed(*dependency))
+    };
+
+    let mut successors: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut in_degree = vec![0; count];
+    for (node, degree) in in_degree.iter_mut().enumerate() {
+        for &(from, _) in edges(node) {
+            successors.entry(from).or_default().push(node);
+            *degree += 1;
+        }
+    }
+
+    let mut ready = (0..count)
+        .filter(|&node| in_degree[node] == 0)
+        .collect::<Vec<_>>();
+    let mut remaining = vec![true; count];
+    while let Some(node) = ready.pop() {
+        remaining[node] = false;
+        for &next in successors.get(&node).into_iter().flatten() {
+            in_degree[next] -= 1;
+            if in_degree[next] == 0 {
+                ready.push(next);
+            }
+        }
+    }
+
+    let mut found = vec![];
+    let mut reported = HashSet::new();
+    for start in (0..count).filter(|&node| remaining[node]) {
+        if reported.contains(&start) {
+            continue;
+        }
+
+        let mut path: Vec<(usize, Dependency)> = vec![];
+        let mut position = HashMap::new();
+        let mut node = start;
+        let cycle = loop {
+            if let Some(&at) = position.get(&node) {
+                break path.split_off(at);
+            }
+
+            let Some(&(from, dependency)) = edges(node).find(|(from, _)| remaining[*from]) else {
+                break vec![];
+            };
+
+            position.insert(node, path.len());
+            path.push((from, dependency));
+            node = from;
+        };
+
+        if cycle.is_empty() || cycle.iter().any(|(node, _)| reported.contains(node)) {
+            continue;
+        }
+
+        reported.extend(cycle.iter().map(|&(node, _)| node));
+        // The walk went backwards along edges; flipped, each entry is a
+        // transaction and the edge leading from it to the next one.
+        let mut cycle = cycle;
+        cycle.reverse();
+        found.push(cycle);
+    }
+
+    found
+}
+
+// Checks a history of `txn` operations over read-write registers, assuming
+// every write puts a value unique to its key. Committed and indeterminate
+// transactions form a dependency graph: a write-read edge runs from a writer
+// to every transaction reading its value, and write-write edges follow the
+// version order of each key. Cycles in that graph are G0 or G1c; aborted and
+// intermediate reads are found directly.
+//
+// A register read only shows the write it observed, so the version order is
+// inferred the way Elle does for registers: a transaction that reads a value
+// and then overwrites it comes after that value's writer, and one that writes
+// a key and later reads another transaction's value of it comes before that
+// writer. Keys whose writers this leaves in no single order are reported, as
+// a G0 cycle through them may have gone unseen.
+pub fn check(history: &History) -> TxnReport {
+    let mut report = TxnReport::default();
+    let transactions = history
+        .operations()
+        .into_iter()
+        .filter(|operation| operation.f == "txn")
+        .enumerate()
+        .map(|(index, operation)| Transaction {
+            index,
+            process: operation.process,
+            outcome: operation.outcome,
+            ops: match operation.outcome {
+                EventKind::Ok => txn_of(operation.output.as_ref().unwrap_or(&Value::Null)),
+                _ => txn_of(&operation.input),
+            },
+        })
+        .collect::<Vec<_>>();
+
+    report.transactions = transactions.len();
+    let mut writes = HashMap::new();
+    for transaction in &transactions {
+        match transaction.outcome {
+            EventKind::Ok => report.committed += 1,
+            EventKind::Fail => report.aborted += 1,
+            _ => report.indeterminate += 1,
+        }
+
+        let mut last = HashMap::new();
+        for (position, op) in transaction.ops.iter().enumerate() {
+            if let MicroOp::Write { key, .. } = op {
+                last.insert(*key, position);
+            }
+        }
+
+        for (position, op) in transaction.ops.iter().enumerate() {
+            if let MicroOp::Write { key, value } = *op {
+                let write = Write {
+                    writer: transaction.index,
+                    last: last[&key] == position,
+                };
+
+                writes.insert((key, value), write);
+            }
+        }
+    }
+
+    let mut predecessors = Graph::new();
+    let mut versions: HashMap<usize, Vec<(usize, usize)>> = HashMap::new();
+    let anomaly = |kind, description: String, involved: &[usize]| Anomaly {
+        kind,
+        description,
+        transactions: involved
+            .iter()
+            .map(|&index| transactions[index].clone())
+            .collect(),
+        edges: vec![],
+    };
+
+    for transaction in transactions.iter().filter(|t| t.outcome == EventKind::Ok) {
+        let reader = transaction.index;
+        for op in &transaction.ops {
+            let MicroOp::Read {
+                key,
+                value: Some(value),
+            } = *op
+            else {
+                continue;
+            };
+
+            let Some(write) = writes.get(&(key, value)) else {
+                report.anomalies.push(anomaly(
+                    AnomalyKind::GarbageRead,
+                    format!("T{reader} read {value} from key {key}, which was never written"),
+                    &[reader],
+                ));
+                continue;
+            };
+
+            if write.writer == reader {
+                continue;
+            }
+
+            if transactions[write.writer].outcome == EventKind::Fail {
+                report.anomalies.push(anomaly(
+                    AnomalyKind::G1a,
+                    format!(
+                        "T{reader} read {value} from key {key}, written by aborted T{}",
+                        write.writer
+                    ),
+                    &[write.writer, reader],
+                ));
+            } else if !write.last {
+                report.anomalies.push(anomaly(
+                    AnomalyKind::G1b,
+                    format!(
+                        "T{reader} read {value} from key {key}, an intermediate write of T{}",
+                        write.writer
+                    ),
+                    &[write.writer, reader],
+                ));
+            }
+        }
+
+        for (key, value) in external_reads(&transaction.ops) {
+            let Some(write) = value.and_then(|value| writes.get(&(key, value))) else {
+                continue;
+            };
+
+            if write.writer == reader || transactions[write.writer].outcome == EventKind::Fail {
+                continue;
+            }
+
+            let edges = predecessors.entry(reader).or_default();
+            edges.push((write.writer, Dependency::WriteRead));
+            let overwritten = transaction
+                .ops
+                .iter()
+                .any(|op| matches!(op, MicroOp::Write { key: written, .. } if *written == key));
+            if overwritten {
+                edges.push((write.writer, Dependency::WriteWrite));
+                versions
+                    .entry(key)
+                    .or_default()
+                    .push((write.writer, reader));
+            }
+        }
+
+        // Reading another transaction's value after writing the key means
+        // that write came later.
+        let mut written = HashSet::new();
+        for op in &transaction.ops {
+            match *op {
+                MicroOp::Write { key, .. } => {
+                    written.insert(key);
+                }
+                MicroOp::Read {
+                    key,
+                    value: Some(value),
+                } if written.contains(&key) => {
+                    let Some(write) = writes.get(&(key, value)) else {
+                        continue;
+                    };
+
+                    if write.writer == reader
+                        || transactions[write.writer].outcome == EventKind::Fail
+                    {
+                        continue;
+                    }
+
+                    predecessors
+                        .entry(write.writer)
+                        .or_default()
+                        .push((reader, Dependency::WriteWrite));
+                    versions
+                        .entry(key)
+                        .or_default()
+                        .push((reader, write.writer));
+                }
+                MicroOp::Read { .. } => {}
+            }
+        }
+    }
+
+    let mut writers: HashMap<usize, HashSet<usize>> = HashMap::new();
+    for (&(key, _), write) in &writes {
+        if transactions[write.writer].outcome != EventKind::Fail {
+            writers.entry(key).or_default().insert(write.writer);
+        }
+    }
+
+    report.unordered_keys = writers
+        .iter()
+        .filter(|(key, writers)| {
+            let edges = versions.get(key).map(Vec::as_slice).unwrap_or_default();
+            !is_ordered(writers, edges)
+        })
+        .map(|(&key, _)| key)
+        .collect();
+    report.unordered_keys.sort_unstable();
+
+    report.dependencies = predecessors.values().map(Vec::len).sum();
+    let mut cycle_anomaly = |kind, cycle: Vec<(usize, Dependency)>| {
+        let description = cycle
+            .iter()
+            .map(|(node, dependency)| format!("T{node} -{dependency}->"))
+            .chain(cycle.first().map(|(node, _)| format!("T{node}")))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let involved = cycle.iter().map(|&(node, _)| node).collect::<Vec<_>>();
+        let mut anomaly = anomaly(kind, description, &involved);
+        anomaly.edges = cycle.iter().map(|&(_, dependency)| dependency).collect();
+        report.anomalies.push(anomaly);
+    };
+
+    let count = transactions.len();
+    let write_cycles = cycles(&predecessors, count, |dependency| {
+        dependency == Dependency::WriteWrite
+    });
+    for cycle in write_cycles {
+        cycle_anomaly(AnomalyKind::G0, cycle);
+    }
+
+    // A cycle through both kinds of edge is G1c, unless every step of it
+    // is also a write-write dependency, in which case it is the G0 above.
+    let write_write = |from: usize, to: usize| {
+        predecessors
+            .get(&to)
+            .is_some_and(|edges| edges.contains(&(from, Dependency::WriteWrite)))
+    };
+
+    for cycle in cycles(&predecessors, count, |_| true) {
+        let labelled = (0..cycle.len())
+            .map(|at| {
+                let (from, _) = cycle[at];
+                let (to, _) = cycle[(at + 1) % cycle.len()];
+                match write_write(from, to) {
+                    true => (from, Dependency::WriteWrite),
+                    false => (from, Dependency::WriteRead),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if labelled
+            .iter()
+            .any(|(_, dependency)| *dependency == Dependency::WriteRead)
+        {
+            cycle_anomaly(AnomalyKind::G1c, labelled);
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::history::Operation;
+
+    // Builds a history of sequential transactions, each given as the
+    // completed micro-ops it returned, or took as input if it failed.
+    fn history(transactions: &[(EventKind, Value)]) -> History {
+        let operations = transactions
+            .iter()
+            .enumerate()
+            .map(|(index, (outcome, txn))| Operation {
+                process: index,
+                f: "txn".to_string(),
+                node: None,
+                input: txn.clone(),
+                output: Some(txn.clone()),
+                outcome: *outcome,
+                invoked_at: 10 * index as u64,
+                completed_at: Some(10 * index as u64 + 5),
+            })
+            .collect::<Vec<_>>();
+
+        History::from_operations(&operations)
+    }
+
+    fn kinds(report: &TxnReport) -> Vec<AnomalyKind> {
+        report
+            .anomalies
+            .iter()
+            .map(|anomaly| anomaly.kind)
+            .collect()
+    }
+
+    #[test]
+    fn accepts_a_clean_history() {
+        let report = check(&history(&[
+            (EventKind::Ok, json!([["w", 1, 1], ["w", 2, 1]])),
+            (EventKind::Ok, json!([["r", 1, 1], ["w", 1, 2]])),
+            (EventKind::Ok, json!([["r", 1, 2], ["r", 2, 1]])),
+        ]));
+
+        assert_eq!(kinds(&report), vec![]);
+        assert_eq!(report.committed, 3);
+        assert!(report.unordered_keys.is_empty());
+    }
+
+    #[test]
+    fn finds_g0_between_writes_ordered_by_later_reads() {
+        // Each transaction reads the other's write to a key it wrote first,
+        // so each write came before the other's on one key.
+        let report = check(&history(&[
+            (
+                EventKind::Ok,
+                json!([["w", 1, 1], ["w", 2, 1], ["r", 2, 2]]),
+            ),
+            (
+                EventKind::Ok,
+                json!([["w", 2, 2], ["w", 1, 2], ["r", 1, 1]]),
+            ),
+        ]));
+
+        assert_eq!(kinds(&report), vec![AnomalyKind::G0]);
+        assert_eq!(
+            report.anomalies[0].edges,
+            vec![Dependency::WriteWrite, Dependency::WriteWrite]
+        );
+        assert_eq!(
+            report.violations(ConsistencyModel::ReadUncommitted).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn finds_g1a() {
+        let report = check(&history(&[
+            (EventKind::Fail, json!([["w", 1, 1]])),
+            (EventKind::Ok, json!([["r", 1, 1]])),
+        ]));
+
+        assert_eq!(kinds(&report), vec![AnomalyKind::G1a]);
+        assert!(report
+            .violations(ConsistencyModel::ReadUncommitted)
+            .is_empty());
+        assert_eq!(report.violations(ConsistencyModel::ReadCommitted).len(), 1);
+    }
+
+    #[test]
+    fn finds_g1b() {
+        let report = check(&history(&[
+            (EventKind::Ok, json!([["w", 1, 1], ["w", 1, 2]])),
+            (EventKind::Ok, json!([["r", 1, 1]])),
+        ]));
+
+        assert_eq!(kinds(&report), vec![AnomalyKind::G1b]);
+    }
+
+    #[test]
+    fn finds_g1c() {
+        let report = check(&history(&[
+            (EventKind::Ok, json!([["w", 1, 1], ["r", 2, 1]])),
+            (EventKind::Ok, json!([["w", 2, 1], ["r", 1, 1]])),
+        ]));
+
+        assert_eq!(kinds(&report), vec![AnomalyKind::G1c]);
+        assert_eq!(
+            report.anomalies[0].edges,
+            vec![Dependency::WriteRead, Dependency::WriteRead]
+        );
+    }
+
+    #[test]
+    fn finds_garbage_reads() {
+        let report = check(&history(&[(EventKind::Ok, json!([["r", 1, 7]]))]));
+        assert_eq!(kinds(&report), vec![AnomalyKind::GarbageRead]);
+    }
+
+    #[test]
+    fn blind_writes_leave_g0_inconclusive() {
+        let report = check(&history(&[
+            (EventKind::Ok, json!([["w", 1, 1], ["w", 2, 1]])),
+            (EventKind::Ok, json!([["w", 1, 2], ["w", 2, 2]])),
+            (EventKind::Ok, json!([["r", 2, 2], ["w", 2, 3]])),
+        ]));
+
+        assert_eq!(kinds(&report), vec![]);
+        assert_eq!(report.unordered_keys, vec![1, 2]);
+        assert!(report.to_string().contains("g0:            inconclusive"));
+    }
+}
@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use maelstrom::{
+    crdt::{Crdt, PnCounter},
+    error::MaelstromError,
+    message::{InitializationRequest, Message},
+    node::MaelstromNode,
+    service::Service,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum CounterRequest {
+    Add { delta: i64 },
+    Read,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum CounterResponse {
+    AddOk,
+    ReadOk { value: i64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum PeerPayload {
+    Gossip { counter: PnCounter },
+}
+
+// Replicates a pn-counter by gossiping its full state; a g-counter is the
+// special case with no decrements.
+struct CounterNode {
+    id: String,
+    network: Vec<String>,
+    counter: PnCounter,
+}
+
+impl MaelstromNode for CounterNode {
+    type InputPayload = CounterRequest;
+    type OutputPayload = CounterResponse;
+    type PeerPayload = PeerPayload;
+    type Config = ();
+
+    fn new(init_message: &Message<InitializationRequest>, _: Self::Config) -> Self {
+        let InitializationRequest::Init { id, neighbors } = init_message.payload();
+        let network = neighbors
+            .iter()
+            .filter(|&neighbor| neighbor != id)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        Self {
+            id: id.clone(),
+            network,
+            counter: PnCounter::default(),
+        }
+    }
+
+    fn handle(
+        &mut self,
+        message: &Message<Self::InputPayload>,
+        _: &mut Service,
+    ) -> Result<Option<Self::OutputPayload>, MaelstromError>
+    where
+        Self: Sized,
+    {
+        match message.payload() {
+            CounterRequest::Add { delta } => {
+                self.counter.add(&self.id, *delta);
+                Ok(Some(CounterResponse::AddOk))
+            }
+            CounterRequest::Read => Ok(Some(CounterResponse::ReadOk {
+                value: self.counter.value(),
+            })),
+        }
+    }
+
+    fn start(&mut self, service: &mut Service) -> Result<(), MaelstromError> {
+        service.every(Duration::from_millis(200));
+        Ok(())
+    }
+
+    fn handle_timer(&mut self, _: usize, service: &mut Service) -> Result<(), MaelstromError> {
+        for neighbor in &self.network {
+            service.peer_rpc(
+                self.id.clone(),
+                neighbor.clone(),
+                PeerPayload::Gossip {
+                    counter: self.counter.clone(),
+                },
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn handle_peer(
+        &mut self,
+        message: &Message<Self::PeerPayload>,
+        _: &mut Service,
+    ) -> Result<Option<Self::PeerPayload>, MaelstromError> {
+        match message.payload() {
+            PeerPayload::Gossip { counter } => {
+                self.counter.merge(counter);
+                Ok(None)
+            }
+        }
+    }
+}
+
+pub fn main() -> anyhow::Result<()> {
+    Service::new().run::<CounterNode>()?;
+    Ok(())
+}
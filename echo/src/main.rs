@@ -25,8 +25,9 @@ impl MaelstromNode for EchoNode {
     type InputPayload = EchoRequest;
     type OutputPayload = EchoResponse;
     type PeerPayload = ();
+    type Config = ();
 
-    fn new(_: &Message<InitializationRequest>) -> Self {
+    fn new(_: &Message<InitializationRequest>, _: Self::Config) -> Self {
         Self
     }
 
@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use maelstrom::{
+    error::{MaelstromError, TEMPORARILY_UNAVAILABLE, TIMEOUT},
+    kv::KvClient,
+    message::{InitializationRequest, Message},
+    node::MaelstromNode,
+    service::Service,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum CounterRequest {
+    Add { delta: u64 },
+    Read,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum CounterResponse {
+    AddOk,
+    ReadOk { value: u64 },
+    Error { code: usize, text: String },
+}
+
+// Every node owns the seq-kv key named after it and is the only one to write
+// it, so writes never conflict. Reads from seq-kv may be stale, but a client's
+// reads never go back past its own last write: rewriting our key before
+// reading pulls in every add acknowledged before the read began.
+struct CounterNode {
+    id: String,
+    nodes: Vec<String>,
+    count: u64,
+    written: u64,
+    kv: KvClient,
+}
+
+impl CounterNode {
+    fn flush(&mut self, service: &mut Service) -> Result<(), MaelstromError> {
+        if self.count != self.written {
+            self.kv.write(service, &self.id, &self.count)?;
+            self.written = self.count;
+        }
+
+        Ok(())
+    }
+
+    fn read(&mut self, service: &mut Service) -> Result<u64, MaelstromError> {
+        self.kv.write(service, &self.id, &self.count)?;
+        self.written = self.count;
+
+        let mut value = self.count;
+        for node in self.nodes.iter().filter(|&node| *node != self.id) {
+            value += self.kv.read::<u64>(service, node)?.unwrap_or_default();
+        }
+
+        Ok(value)
+    }
+}
+
+fn is_unavailable(error: &MaelstromError) -> bool {
+    matches!(error, MaelstromError::Timeout | MaelstromError::RpcError(_))
+}
+
+impl MaelstromNode for CounterNode {
+    type InputPayload = CounterRequest;
+    type OutputPayload = CounterResponse;
+    type PeerPayload = ();
+    type Config = ();
+
+    fn new(init_message: &Message<InitializationRequest>, _: Self::Config) -> Self {
+        let InitializationRequest::Init { id, neighbors } = init_message.payload();
+        Self {
+            id: id.clone(),
+            nodes: neighbors.clone(),
+            count: 0,
+            written: 0,
+            kv: KvClient::seq(),
+        }
+    }
+
+    fn handle(
+        &mut self,
+        message: &Message<Self::InputPayload>,
+        service: &mut Service,
+    ) -> Result<Option<Self::OutputPayload>, MaelstromError>
+    where
+        Self: Sized,
+    {
+        match message.payload() {
+            // A delta that could not be written stays in `count` and goes out
+            // with the next flush, so the add may still take effect.
+            CounterRequest::Add { delta } => {
+                self.count += delta;
+                match self.flush(service) {
+                    Ok(()) => Ok(Some(CounterResponse::AddOk)),
+                    Err(error) if is_unavailable(&error) => Ok(Some(CounterResponse::Error {
+                        code: TIMEOUT,
+                        text: error.to_string(),
+                    })),
+                    Err(error) => Err(error),
+                }
+            }
+            CounterRequest::Read => match self.read(service) {
+                Ok(value) => Ok(Some(CounterResponse::ReadOk { value })),
+                Err(error) if is_unavailable(&error) => Ok(Some(CounterResponse::Error {
+                    code: TEMPORARILY_UNAVAILABLE,
+                    text: error.to_string(),
+                })),
+                Err(error) => Err(error),
+            },
+        }
+    }
+
+    fn start(&mut self, service: &mut Service) -> Result<(), MaelstromError> {
+        service.every(Duration::from_millis(500));
+        Ok(())
+    }
+
+    fn handle_timer(&mut self, _: usize, service: &mut Service) -> Result<(), MaelstromError> {
+        match self.flush(service) {
+            Err(error) if !is_unavailable(&error) => Err(error),
+            _ => Ok(()),
+        }
+    }
+}
+
+pub fn main() -> anyhow::Result<()> {
+    Service::new().run::<CounterNode>()?;
+    Ok(())
+}
@@ -0,0 +1,234 @@
+mod profile;
+
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, ExitCode},
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Context};
+
+use checker::results::MaelstromResults;
+use profile::{Profile, PROFILES};
+use simulator::simulation::{self, Config};
+
+const USAGE: &str = "usage:
+  glomers list
+  glomers run <profile> [--maelstrom DIR] [--debug] [--node-count N] [--time-limit S] \
+[--rate R] [--latency MS] [--partitions | --no-partitions] [--strategy NAME] [--topology NAME]
+STRATEGY: naive-flood, reliable-flood, ack-flood, full-state, delta, probabilistic-resend, plumtree, push-pull, merkle
+TOPOLOGY: full, maelstrom, spanning-tree, tree:K, grid, ring-with-chords, random:K";
+
+struct Options {
+    maelstrom: Option<PathBuf>,
+    release: bool,
+    // Environment for the node binary on top of the profile's.
+    env: Vec<(String, String)>,
+}
+
+impl Options {
+    fn env<'a>(&'a self, profile: &'a Profile) -> impl Iterator<Item = (&'a str, &'a str)> {
+        profile.env.iter().copied().chain(
+            self.env
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.as_str())),
+        )
+    }
+}
+
+fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("glomers lives inside the workspace")
+        .to_path_buf()
+}
+
+fn flag_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> anyhow::Result<T> {
+    value
+        .ok_or_else(|| anyhow!("{flag} expects a value"))?
+        .parse()
+        .map_err(|_| anyhow!("{flag} got an invalid value"))
+}
+
+fn parse_run(mut args: impl Iterator<Item = String>) -> anyhow::Result<(Profile, Options)> {
+    let name = args.next().ok_or_else(|| anyhow!(USAGE))?;
+    let mut profile = profile::find(&name)
+        .cloned()
+        .ok_or_else(|| anyhow!("unknown profile {name}, see `glomers list`"))?;
+
+    let mut options = Options {
+        maelstrom: None,
+        release: true,
+        env: vec![],
+    };
+
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--maelstrom" => options.maelstrom = Some(flag_value(&flag, args.next())?),
+            "--debug" => options.release = false,
+            "--node-count" => profile.node_count = flag_value(&flag, args.next())?,
+            "--time-limit" => profile.time_limit = flag_value(&flag, args.next())?,
+            "--rate" => profile.rate = flag_value(&flag, args.next())?,
+            "--latency" => profile.latency = flag_value(&flag, args.next())?,
+            "--partitions" => profile.partitions = true,
+            "--no-partitions" => profile.partitions = false,
+            "--strategy" => {
+                let strategy: String = flag_value(&flag, args.next())?;
+                options
+                    .env
+                    .push((String::from("BROADCAST_STRATEGY"), strategy));
+            }
+            "--topology" => {
+                let topology: String = flag_value(&flag, args.next())?;
+                options
+                    .env
+                    .push((String::from("BROADCAST_TOPOLOGY"), topology));
+            }
+            _ => bail!("unknown flag {flag}\n{USAGE}"),
+        }
+    }
+
+    Ok((profile, options))
+}
+
+fn build(root: &Path, profile: &Profile, options: &Options) -> anyhow::Result<PathBuf> {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| String::from("cargo"));
+    let mut command = Command::new(cargo);
+    command
+        .current_dir(root)
+        .args(["build", "--package", profile.package]);
+    if options.release {
+        command.arg("--release");
+    }
+
+    let status = command.status().context("failed to run cargo")?;
+    if !status.success() {
+        bail!("building {} failed", profile.package);
+    }
+
+    let target = std::env::var("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| root.join("target"));
+    let mode = if options.release { "release" } else { "debug" };
+    Ok(target.join(mode).join(profile.package))
+}
+
+fn run_maelstrom(
+    root: &Path,
+    maelstrom: &Path,
+    binary: &Path,
+    profile: &Profile,
+    options: &Options,
+) -> anyhow::Result<bool> {
+    let mut command = Command::new(maelstrom.join("maelstrom"));
+    command.current_dir(root).arg("test");
+    command.args(["-w", profile.workload]);
+    command.arg("--bin").arg(binary);
+    command.args(["--node-count", &profile.node_count.to_string()]);
+    command.args(["--time-limit", &profile.time_limit.to_string()]);
+    command.args(["--rate", &profile.rate.to_string()]);
+    command.args(["--latency", &profile.latency.to_string()]);
+    if profile.partitions {
+        command.args(["--nemesis", "partition"]);
+    }
+
+    command.args(profile.maelstrom_args);
+    command.envs(options.env(profile));
+
+    let status = command.status().context("failed to run maelstrom")?;
+    let results_path = root.join("store/latest/results.edn");
+    let input = std::fs::read_to_string(&results_path)
+        .with_context(|| format!("failed to read {}", results_path.display()))?;
+    let results = MaelstromResults::parse(&input)?;
+
+    println!("{results}");
+    let violations = results.violations(&profile.thresholds());
+    for violation in &violations {
+        println!("FAIL: {violation}");
+    }
+
+    println!("results: {}", results_path.display());
+    Ok(status.success() && violations.is_empty())
+}
+
+fn run_simulator(
+    root: &Path,
+    binary: PathBuf,
+    profile: &Profile,
+    options: &Options,
+) -> anyhow::Result<bool> {
+    let store = root.join("store/sim").join(profile.name);
+    std::fs::create_dir_all(&store)
+        .with_context(|| format!("failed to create {}", store.display()))?;
+
+    let mut config = Config::new(binary, profile.node_count);
+    config.time_limit = Duration::from_secs(profile.time_limit);
+    config.rate = profile.rate;
+    config.latency = Duration::from_millis(profile.latency);
+    config.partitions = profile.partitions;
+    config.store = Some(store.clone());
+    config.env = options
+        .env(profile)
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+
+    let mut workload = profile.simulated_workload();
+    let report = simulation::run(&config, workload.as_mut())?;
+
+    println!("{}", report.check.summary);
+    for problem in &report.check.problems {
+        println!("FAIL: {problem}");
+    }
+
+    println!("history: {}", store.join("history.jsonl").display());
+    Ok(report.check.valid)
+}
+
+fn run(args: impl Iterator<Item = String>) -> anyhow::Result<bool> {
+    let (profile, options) = parse_run(args)?;
+    let root = workspace_root();
+    let binary = build(&root, &profile, &options)?;
+
+    println!(
+        "{}: {} on {} node(s) for {}s at {} ops/s, {}ms latency{}",
+        profile.name,
+        profile.workload,
+        profile.node_count,
+        profile.time_limit,
+        profile.rate,
+        profile.latency,
+        if profile.partitions {
+            ", partitions"
+        } else {
+            ""
+        }
+    );
+
+    match &options.maelstrom {
+        Some(maelstrom) => run_maelstrom(&root, maelstrom, &binary, &profile, &options),
+        None => run_simulator(&root, binary, &profile, &options),
+    }
+}
+
+pub fn main() -> anyhow::Result<ExitCode> {
+    let mut args = std::env::args().skip(1);
+    let passed = match args.next().as_deref() {
+        Some("list") => {
+            for profile in PROFILES {
+                println!("{:<12} {}", profile.name, profile.description);
+            }
+
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some("run") => run(args)?,
+        _ => bail!(USAGE),
+    };
+
+    Ok(if passed {
+        println!("PASS");
+        ExitCode::SUCCESS
+    } else {
+        println!("FAIL");
+        ExitCode::FAILURE
+    })
+}
@@ -0,0 +1,292 @@
+use checker::{broadcast::Thresholds, txn::ConsistencyModel};
+use simulator::workload::{
+    BroadcastWorkload, CounterWorkload, EchoWorkload, KafkaWorkload, TxnWorkload,
+    UniqueIdsWorkload, Workload,
+};
+
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub package: &'static str,
+    pub workload: &'static str,
+    pub node_count: usize,
+    pub time_limit: u64,
+    pub rate: f64,
+    pub latency: u64,
+    pub partitions: bool,
+    // Extra environment for the node binary and extra `maelstrom test` flags.
+    pub env: &'static [(&'static str, &'static str)],
+    pub maelstrom_args: &'static [&'static str],
+}
+
+impl Profile {
+    pub fn thresholds(&self) -> Thresholds {
+        match self.name {
+            "3d" => Thresholds::challenge_3d(),
+            "3e" => Thresholds::challenge_3e(),
+            // Counters may serve stale reads while partitioned, so only the
+            // final reads are held to the acknowledged adds.
+            _ => Thresholds::default(),
+        }
+    }
+
+    pub fn txn_model(&self) -> ConsistencyModel {
+        match self.name {
+            "6a" | "6b" => ConsistencyModel::ReadUncommitted,
+            _ => ConsistencyModel::ReadCommitted,
+        }
+    }
+
+    pub fn simulated_workload(&self) -> Box<dyn Workload> {
+        match self.workload {
+            "echo" => Box::<EchoWorkload>::default(),
+            "unique-ids" => Box::<UniqueIdsWorkload>::default(),
+            "g-counter" => Box::new(CounterWorkload::grow_only(self.thresholds())),
+            "pn-counter" => Box::new(CounterWorkload::pn(self.thresholds())),
+            "kafka" => Box::<KafkaWorkload>::default(),
+            "txn-rw-register" => Box::new(TxnWorkload::default().with_model(self.txn_model())),
+            _ => Box::new(BroadcastWorkload::new(self.thresholds())),
+        }
+    }
+}
+
+pub const PROFILES: &[Profile] = &[
+    Profile {
+        name: "echo",
+        description: "challenge 1: echo",
+        package: "echo",
+        workload: "echo",
+        node_count: 1,
+        time_limit: 10,
+        rate: 10.0,
+        latency: 0,
+        partitions: false,
+        env: &[],
+        maelstrom_args: &[],
+    },
+    Profile {
+        name: "unique-ids",
+        description: "challenge 2: unique id generation under partitions",
+        package: "unique_id",
+        workload: "unique-ids",
+        node_count: 3,
+        time_limit: 30,
+        rate: 1000.0,
+        latency: 0,
+        partitions: true,
+        env: &[],
+        maelstrom_args: &[],
+    },
+    Profile {
+        name: "3a",
+        description: "challenge 3a: single-node broadcast",
+        package: "broadcast",
+        workload: "broadcast",
+        node_count: 1,
+        time_limit: 20,
+        rate: 10.0,
+        latency: 0,
+        partitions: false,
+        env: &[("BROADCAST_STRATEGY", "naive-flood")],
+        maelstrom_args: &[],
+    },
+    Profile {
+        name: "3b",
+        description: "challenge 3b: multi-node broadcast",
+        package: "broadcast",
+        workload: "broadcast",
+        node_count: 5,
+        time_limit: 20,
+        rate: 10.0,
+        latency: 0,
+        partitions: false,
+        env: &[
+            ("BROADCAST_STRATEGY", "naive-flood"),
+            ("BROADCAST_BATCH_MS", "50"),
+        ],
+        maelstrom_args: &[],
+    },
+    Profile {
+        name: "3c",
+        description: "challenge 3c: fault-tolerant broadcast",
+        package: "broadcast",
+        workload: "broadcast",
+        node_count: 5,
+        time_limit: 20,
+        rate: 10.0,
+        latency: 0,
+        partitions: true,
+        env: &[("BROADCAST_STRATEGY", "delta")],
+        maelstrom_args: &[],
+    },
+    Profile {
+        name: "3d",
+        description: "challenge 3d: efficient broadcast, part I",
+        package: "broadcast",
+        workload: "broadcast",
+        node_count: 25,
+        time_limit: 20,
+        rate: 100.0,
+        latency: 100,
+        partitions: false,
+        env: &[
+            ("BROADCAST_STRATEGY", "delta"),
+            ("BROADCAST_INTERVAL_MS", "200"),
+        ],
+        maelstrom_args: &[],
+    },
+    Profile {
+        name: "3e",
+        description: "challenge 3e: efficient broadcast, part II",
+        package: "broadcast",
+        workload: "broadcast",
+        node_count: 25,
+        time_limit: 20,
+        rate: 100.0,
+        latency: 100,
+        partitions: false,
+        env: &[
+            ("BROADCAST_STRATEGY", "push-pull"),
+            ("BROADCAST_INTERVAL_MS", "300"),
+            ("BROADCAST_FANOUT", "3"),
+        ],
+        maelstrom_args: &[],
+    },
+    Profile {
+        name: "4",
+        description: "challenge 4: grow-only counter on seq-kv",
+        package: "g_counter",
+        workload: "g-counter",
+        node_count: 3,
+        time_limit: 20,
+        rate: 100.0,
+        latency: 0,
+        partitions: true,
+        env: &[],
+        maelstrom_args: &[],
+    },
+    Profile {
+        name: "4-crdt",
+        description: "challenge 4 with a gossip-only g-counter crdt",
+        package: "crdt_counter",
+        workload: "g-counter",
+        node_count: 3,
+        time_limit: 20,
+        rate: 100.0,
+        latency: 0,
+        partitions: true,
+        env: &[],
+        maelstrom_args: &[],
+    },
+    Profile {
+        name: "pn-counter",
+        description: "gossip-only pn-counter crdt",
+        package: "crdt_counter",
+        workload: "pn-counter",
+        node_count: 3,
+        time_limit: 20,
+        rate: 100.0,
+        latency: 0,
+        partitions: true,
+        env: &[],
+        maelstrom_args: &[],
+    },
+    Profile {
+        name: "5a",
+        description: "challenge 5a: single-node kafka-style log",
+        package: "kafka",
+        workload: "kafka",
+        node_count: 1,
+        time_limit: 20,
+        rate: 1000.0,
+        latency: 0,
+        partitions: false,
+        env: &[],
+        maelstrom_args: &[],
+    },
+    Profile {
+        name: "5b",
+        description: "challenge 5b: multi-node kafka-style log",
+        package: "kafka",
+        workload: "kafka",
+        node_count: 2,
+        time_limit: 20,
+        rate: 1000.0,
+        latency: 0,
+        partitions: false,
+        env: &[("KAFKA_OFFSETS", "lin-kv")],
+        maelstrom_args: &[],
+    },
+    Profile {
+        name: "5c",
+        description: "challenge 5c: efficient kafka-style log",
+        package: "kafka",
+        workload: "kafka",
+        node_count: 2,
+        time_limit: 20,
+        rate: 1000.0,
+        latency: 0,
+        partitions: false,
+        env: &[],
+        maelstrom_args: &[],
+    },
+    Profile {
+        name: "6a",
+        description: "challenge 6a: single-node, totally-available transactions",
+        package: "txn",
+        workload: "txn-rw-register",
+        node_count: 1,
+        time_limit: 20,
+        rate: 1000.0,
+        latency: 0,
+        partitions: false,
+        env: &[],
+        maelstrom_args: &[
+            "--consistency-models",
+            "read-uncommitted",
+            "--availability",
+            "total",
+        ],
+    },
+    Profile {
+        name: "6b",
+        description: "challenge 6b: totally-available, read-uncommitted transactions",
+        package: "txn",
+        workload: "txn-rw-register",
+        node_count: 2,
+        time_limit: 20,
+        rate: 1000.0,
+        latency: 0,
+        partitions: true,
+        env: &[("TXN_ISOLATION", "read-uncommitted")],
+        maelstrom_args: &[
+            "--consistency-models",
+            "read-uncommitted",
+            "--availability",
+            "total",
+        ],
+    },
+    Profile {
+        name: "6c",
+        description: "challenge 6c: totally-available, read-committed transactions",
+        package: "txn",
+        workload: "txn-rw-register",
+        node_count: 2,
+        time_limit: 20,
+        rate: 1000.0,
+        latency: 0,
+        partitions: true,
+        env: &[("TXN_ISOLATION", "read-committed")],
+        maelstrom_args: &[
+            "--consistency-models",
+            "read-committed",
+            "--availability",
+            "total",
+        ],
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Profile> {
+    PROFILES.iter().find(|profile| profile.name == name)
+}
@@ -0,0 +1,440 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use maelstrom::{
+    config::{NodeConfig, Settings},
+    error::{MaelstromError, TEMPORARILY_UNAVAILABLE, TIMEOUT},
+    kv::KvClient,
+    message::{InitializationRequest, Message},
+    node::MaelstromNode,
+    service::Service,
+};
+
+// The most messages a single poll returns per key; clients poll again from
+// the last offset they saw.
+const MAX_POLL: usize = 64;
+
+// How often forwarded sends the owner has not answered are given up on; each
+// gets at least this long.
+const FORWARD_EXPIRY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum KafkaRequest {
+    Send { key: String, msg: usize },
+    Poll { offsets: HashMap<String, usize> },
+    CommitOffsets { offsets: HashMap<String, usize> },
+    ListCommittedOffsets { keys: Vec<String> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(clippy::enum_variant_names)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum KafkaResponse {
+    SendOk {
+        offset: usize,
+    },
+    PollOk {
+        msgs: HashMap<String, Vec<(usize, usize)>>,
+    },
+    CommitOffsetsOk,
+    ListCommittedOffsetsOk {
+        offsets: HashMap<String, usize>,
+    },
+    Error {
+        code: usize,
+        text: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum PeerPayload {
+    Forward {
+        key: String,
+        msg: usize,
+    },
+    ForwardOk {
+        offset: usize,
+    },
+    Replicate {
+        key: String,
+        offset: usize,
+        msg: usize,
+    },
+}
+
+// FNV-1a, so that every node maps a key to the same owner.
+fn hash(key: &str) -> u64 {
+    key.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn log_key(key: &str, offset: usize) -> String {
+    format!("log/{key}/{offset}")
+}
+
+fn committed_key(key: &str) -> String {
+    format!("committed/{key}")
+}
+
+// How a send gets its offset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Offsets {
+    // Any node claims the next free offset by creating its entry in lin-kv,
+    // which stays the source of truth for the log.
+    LinKv,
+    // The key's owner assigns offsets itself, so lin-kv only holds committed
+    // offsets.
+    Owner,
+}
+
+impl Offsets {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "lin-kv" => Some(Self::LinKv),
+            "owner" => Some(Self::Owner),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct KafkaConfig {
+    offsets: Offsets,
+    // How often replicated entries a peer has not acknowledged are resent.
+    retransmit: Duration,
+}
+
+impl NodeConfig for KafkaConfig {
+    const PREFIX: &'static str = "KAFKA";
+
+    fn load(settings: &mut Settings) -> Result<Self, MaelstromError> {
+        Ok(Self {
+            offsets: settings.parse(
+                "offsets",
+                Offsets::Owner,
+                "lin-kv or owner",
+                Offsets::parse,
+            )?,
+            retransmit: settings.millis(
+                "retransmit-ms",
+                Duration::from_millis(250),
+                Duration::from_millis(1),
+                Duration::from_secs(60),
+            )?,
+        })
+    }
+}
+
+struct Forwarded {
+    client: Message<KafkaRequest>,
+    // Whether the send was already waiting at the last expiry tick.
+    due: bool,
+}
+
+// With owner offsets, every key is owned by one node, found by hashing the
+// key over the cluster. Sends are forwarded to the owner, which assigns
+// offsets in order. With lin-kv offsets, the node a send arrives at claims an
+// offset in lin-kv, moving on to the next one while the entry already exists,
+// and polls read through to lin-kv for anything not replicated yet.
+//
+// Either way, entries are replicated to the other nodes over reliable links.
+// Polls answer from the local copy of the log up to its first gap, so a
+// replica that is behind returns fewer messages until the rest arrive, but
+// never skips one. Committed offsets live in lin-kv.
+struct KafkaNode {
+    id: String,
+    offsets: Offsets,
+    retransmit: Duration,
+    nodes: Vec<String>,
+    network: Vec<String>,
+    logs: HashMap<String, BTreeMap<usize, usize>>,
+    forwarded: HashMap<usize, Forwarded>,
+    kv: KvClient,
+}
+
+impl KafkaNode {
+    fn owner(&self, key: &str) -> &str {
+        &self.nodes[(hash(key) % self.nodes.len() as u64) as usize]
+    }
+
+    fn append(
+        &mut self,
+        service: &mut Service,
+        key: &str,
+        msg: usize,
+    ) -> Result<usize, MaelstromError> {
+        let log = self.logs.entry(key.to_string()).or_default();
+        let mut offset = log.last_key_value().map_or(0, |(&offset, _)| offset + 1);
+        if self.offsets == Offsets::LinKv {
+            while !self
+                .kv
+                .cas(service, &log_key(key, offset), &None, &Some(msg), true)?
+            {
+                offset += 1;
+            }
+        }
+
+        log.insert(offset, msg);
+        for neighbor in &self.network {
+            service.send_reliable(
+                neighbor,
+                PeerPayload::Replicate {
+                    key: key.to_string(),
+                    offset,
+                    msg,
+                },
+            )?;
+        }
+
+        Ok(offset)
+    }
+
+    fn poll(
+        &mut self,
+        service: &mut Service,
+        key: &str,
+        from: usize,
+    ) -> Result<Vec<(usize, usize)>, MaelstromError> {
+        let log = self.logs.entry(key.to_string()).or_default();
+        let mut msgs = vec![];
+        for offset in from..from + MAX_POLL {
+            let msg = match log.get(&offset) {
+                Some(&msg) => msg,
+                None if self.offsets == Offsets::LinKv => {
+                    match self.kv.read(service, &log_key(key, offset))? {
+                        Some(msg) => *log.entry(offset).or_insert(msg),
+                        None => break,
+                    }
+                }
+                None => break,
+            };
+
+            msgs.push((offset, msg));
+        }
+
+        Ok(msgs)
+    }
+
+    fn commit(
+        &self,
+        service: &mut Service,
+        key: &str,
+        offset: usize,
+    ) -> Result<(), MaelstromError> {
+        loop {
+            let committed = self.kv.read::<usize>(service, &committed_key(key))?;
+            if committed.is_some_and(|committed| committed >= offset) {
+                return Ok(());
+            }
+
+            if self.kv.cas(
+                service,
+                &committed_key(key),
+                &committed,
+                &Some(offset),
+                true,
+            )? {
+                return Ok(());
+            }
+        }
+    }
+
+    fn try_handle(
+        &mut self,
+        request: &KafkaRequest,
+        service: &mut Service,
+    ) -> Result<KafkaResponse, MaelstromError> {
+        match request {
+            KafkaRequest::Send { key, msg } => Ok(KafkaResponse::SendOk {
+                offset: self.append(service, key, *msg)?,
+            }),
+            KafkaRequest::Poll { offsets } => {
+                let mut msgs = HashMap::new();
+                for (key, &offset) in offsets {
+                    let polled = self.poll(service, key, offset)?;
+                    if !polled.is_empty() {
+                        msgs.insert(key.clone(), polled);
+                    }
+                }
+
+                Ok(KafkaResponse::PollOk { msgs })
+            }
+            KafkaRequest::CommitOffsets { offsets } => {
+                for (key, &offset) in offsets {
+                    self.commit(service, key, offset)?;
+                }
+
+                Ok(KafkaResponse::CommitOffsetsOk)
+            }
+            KafkaRequest::ListCommittedOffsets { keys } => {
+                let mut offsets = HashMap::new();
+                for key in keys {
+                    if let Some(offset) = self.kv.read(service, &committed_key(key))? {
+                        offsets.insert(key.clone(), offset);
+                    }
+                }
+
+                Ok(KafkaResponse::ListCommittedOffsetsOk { offsets })
+            }
+        }
+    }
+}
+
+impl MaelstromNode for KafkaNode {
+    type InputPayload = KafkaRequest;
+    type OutputPayload = KafkaResponse;
+    type PeerPayload = PeerPayload;
+    type Config = KafkaConfig;
+
+    fn new(init_message: &Message<InitializationRequest>, config: Self::Config) -> Self {
+        let InitializationRequest::Init { id, neighbors } = init_message.payload();
+        let network = neighbors
+            .iter()
+            .filter(|&neighbor| neighbor != id)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        Self {
+            id: id.clone(),
+            offsets: config.offsets,
+            retransmit: config.retransmit,
+            nodes: neighbors.clone(),
+            network,
+            logs: HashMap::new(),
+            forwarded: HashMap::new(),
+            kv: KvClient::lin(),
+        }
+    }
+
+    fn handle(
+        &mut self,
+        message: &Message<Self::InputPayload>,
+        service: &mut Service,
+    ) -> Result<Option<Self::OutputPayload>, MaelstromError>
+    where
+        Self: Sized,
+    {
+        let request = message.payload();
+        if let (KafkaRequest::Send { key, msg }, Offsets::Owner) = (request, self.offsets) {
+            let owner = self.owner(key).to_string();
+            if owner != self.id {
+                // Forwarding is asynchronous: the client is answered once the
+                // owner replies, so that two nodes forwarding to each other
+                // never wait on one another.
+                let forwarded = Forwarded {
+                    client: message.clone(),
+                    due: false,
+                };
+
+                self.forwarded.insert(service.outbox_id(), forwarded);
+                service.peer_rpc(
+                    self.id.clone(),
+                    owner,
+                    PeerPayload::Forward {
+                        key: key.clone(),
+                        msg: *msg,
+                    },
+                )?;
+
+                return Ok(None);
+            }
+        }
+
+        match self.try_handle(request, service) {
+            Ok(response) => Ok(Some(response)),
+            // Reads have no effect and can fail outright; a commit may have
+            // reached lin-kv before the failure.
+            Err(error @ (MaelstromError::Timeout | MaelstromError::RpcError(_))) => {
+                let code = match request {
+                    KafkaRequest::Poll { .. } | KafkaRequest::ListCommittedOffsets { .. } => {
+                        TEMPORARILY_UNAVAILABLE
+                    }
+                    _ => TIMEOUT,
+                };
+
+                Ok(Some(KafkaResponse::Error {
+                    code,
+                    text: error.to_string(),
+                }))
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    fn start(&mut self, service: &mut Service) -> Result<(), MaelstromError> {
+        service.every(FORWARD_EXPIRY);
+        service.retransmit_every(self.retransmit);
+        Ok(())
+    }
+
+    // Gives up on forwarded sends the owner has not answered since the last
+    // tick, so a lost forward or reply does not hold on to the client's
+    // message for good. The send may still have been applied, so the client
+    // is told the outcome is unknown.
+    fn handle_timer(&mut self, _: usize, service: &mut Service) -> Result<(), MaelstromError> {
+        let expired = self
+            .forwarded
+            .iter()
+            .filter(|(_, forwarded)| forwarded.due)
+            .map(|(&message_id, _)| message_id)
+            .collect::<Vec<_>>();
+
+        for message_id in expired {
+            if let Some(Forwarded { client, .. }) = self.forwarded.remove(&message_id) {
+                let response = KafkaResponse::Error {
+                    code: TIMEOUT,
+                    text: "the key's owner did not answer the forwarded send".into(),
+                };
+
+                service.respond_to(&client, response)?;
+            }
+        }
+
+        for forwarded in self.forwarded.values_mut() {
+            forwarded.due = true;
+        }
+
+        Ok(())
+    }
+
+    fn handle_peer(
+        &mut self,
+        message: &Message<Self::PeerPayload>,
+        service: &mut Service,
+    ) -> Result<Option<Self::PeerPayload>, MaelstromError> {
+        match message.payload() {
+            PeerPayload::Forward { key, msg } => Ok(Some(PeerPayload::ForwardOk {
+                offset: self.append(service, key, *msg)?,
+            })),
+            PeerPayload::ForwardOk { offset } => {
+                let client = message
+                    .in_reply_to()
+                    .and_then(|message_id| self.forwarded.remove(&message_id));
+                if let Some(Forwarded { client, .. }) = client {
+                    service.respond_to(&client, KafkaResponse::SendOk { offset: *offset })?;
+                }
+
+                Ok(None)
+            }
+            PeerPayload::Replicate { key, offset, msg } => {
+                self.logs
+                    .entry(key.clone())
+                    .or_default()
+                    .insert(*offset, *msg);
+                Ok(None)
+            }
+        }
+    }
+}
+
+pub fn main() -> anyhow::Result<()> {
+    Service::new().run::<KafkaNode>()?;
+    Ok(())
+}
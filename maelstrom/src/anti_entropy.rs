@@ -0,0 +1,228 @@
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeSet},
+    hash::{Hash, Hasher},
+    ops::Bound,
+};
+
+use serde::{Deserialize, Serialize};
+
+// The values from `start` up to, but not including, `end`; a missing bound
+// leaves that side open.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span<T> {
+    pub start: Option<T>,
+    pub end: Option<T>,
+}
+
+impl<T> Span<T> {
+    fn bounds(&self) -> (Bound<&T>, Bound<&T>) {
+        let start = self
+            .start
+            .as_ref()
+            .map_or(Bound::Unbounded, Bound::Included);
+        let end = self.end.as_ref().map_or(Bound::Unbounded, Bound::Excluded);
+        (start, end)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Summary<T> {
+    pub span: Span<T>,
+    pub count: usize,
+    pub hash: u64,
+}
+
+// A compact description of a set: its size and hash, plus the size and hash
+// of each of a few spans that together cover every value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Digest<T> {
+    pub count: usize,
+    pub hash: u64,
+    pub spans: Vec<Summary<T>>,
+}
+
+// Push-pull anti-entropy over a set. A node sends a peer its digest; the peer
+// compares it with its own values and, only for the spans that differ, pushes
+// the values it has there and pulls the node's. The node merges those and
+// answers with the values in the spans the peer lacks. Replicas that agree
+// exchange a single digest, however many values they hold.
+pub struct AntiEntropy<T> {
+    values: BTreeSet<T>,
+    spans: usize,
+}
+
+impl<T: Clone + Ord + Hash> Default for AntiEntropy<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone + Ord + Hash> AntiEntropy<T> {
+    pub fn new() -> Self {
+        Self {
+            values: BTreeSet::new(),
+            spans: 16,
+        }
+    }
+
+    // How many spans a digest divides the values into. More spans make
+    // digests larger but narrow down what is exchanged on a mismatch.
+    pub fn with_spans(mut self, spans: usize) -> Self {
+        self.spans = spans.max(1);
+        self
+    }
+
+    pub fn values(&self) -> &BTreeSet<T> {
+        &self.values
+    }
+
+    pub fn insert(&mut self, value: T) -> bool {
+        self.values.insert(value)
+    }
+
+    pub fn merge(&mut self, values: impl IntoIterator<Item = T>) {
+        self.values.extend(values);
+    }
+
+    // Spans hold equal shares of the values, so they split where the values
+    // are rather than where the domain is.
+    pub fn digest(&self) -> Digest<T> {
+        let per_span = self.values.len().div_ceil(self.spans).max(1);
+        let starts = self
+            .values
+            .iter()
+            .step_by(per_span)
+            .skip(1)
+            .cloned()
+            .map(Some);
+
+        let mut spans = vec![];
+        let mut start = None;
+        for end in starts.chain([None]) {
+            let span = Span { start, end };
+            let (count, hash) = self.summarize(&span);
+            start = span.end.clone();
+            spans.push(Summary { span, count, hash });
+        }
+
+        let (count, hash) = self.summarize(&Span {
+            start: None,
+            end: None,
+        });
+
+        Digest { count, hash, spans }
+    }
+
+    // The spans of `digest` that differ from this node's values, with this
+    // node's values in them, or `None` when the sets match.
+    pub fn compare(&self, digest: &Digest<T>) -> Option<(Vec<Span<T>>, Vec<T>)> {
+        let whole = Span {
+            start: None,
+            end: None,
+        };
+
+        if self.summarize(&whole) == (digest.count, digest.hash) {
+            return None;
+        }
+
+        let spans = digest
+            .spans
+            .iter()
+            .filter(|summary| self.summarize(&summary.span) != (summary.count, summary.hash))
+            .map(|summary| summary.span.clone())
+            .collect::<Vec<_>>();
+
+        let values = self.within(&spans).cloned().collect();
+        Some((spans, values))
+    }
+
+    // Merges the values a peer pushed for `spans` and returns those this node
+    // has there that the peer lacks.
+    pub fn reconcile(&mut self, spans: &[Span<T>], pushed: Vec<T>) -> Vec<T> {
+        let pushed = pushed.into_iter().collect::<BTreeSet<_>>();
+        let missing = self
+            .within(spans)
+            .filter(|value| !pushed.contains(*value))
+            .cloned()
+            .collect();
+
+        self.values.extend(pushed);
+        missing
+    }
+
+    fn within<'a>(&'a self, spans: &'a [Span<T>]) -> impl Iterator<Item = &'a T> {
+        spans
+            .iter()
+            .flat_map(|span| self.values.range::<T, _>(span.bounds()))
+    }
+
+    fn summarize(&self, span: &Span<T>) -> (usize, u64) {
+        self.values
+            .range::<T, _>(span.bounds())
+            .fold((0, 0), |(count, hash), value| {
+                let mut hasher = DefaultHasher::new();
+                value.hash(&mut hasher);
+                (count + 1, hash ^ hasher.finish())
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(values: impl IntoIterator<Item = usize>) -> AntiEntropy<usize> {
+        let mut set = AntiEntropy::new().with_spans(8);
+        set.merge(values);
+        set
+    }
+
+    // One round: `a` sends its digest, `b` pushes the values in the spans
+    // that differ, and `a` answers with those `b` lacks there. Returns how
+    // many values were sent.
+    fn exchange(a: &mut AntiEntropy<usize>, b: &mut AntiEntropy<usize>) -> usize {
+        let Some((spans, pushed)) = b.compare(&a.digest()) else {
+            return 0;
+        };
+
+        let sent = pushed.len();
+        let missing = a.reconcile(&spans, pushed);
+        let sent = sent + missing.len();
+        b.merge(missing);
+        sent
+    }
+
+    #[test]
+    fn diverged_sets_converge_in_one_exchange() {
+        let mut a = set((0..1000).chain([5000, 5001]));
+        let mut b = set((0..1000).filter(|value| value % 250 != 0).chain([7000]));
+
+        let sent = exchange(&mut a, &mut b);
+        assert_eq!(a.values(), b.values());
+        assert!(a.values().contains(&7000) && b.values().contains(&5000));
+        // Only the spans holding a difference are sent, not the whole set.
+        assert!(sent < 1000, "sent {sent} values");
+
+        assert_eq!(exchange(&mut a, &mut b), 0);
+    }
+
+    #[test]
+    fn matching_sets_exchange_nothing() {
+        let a = set(0..100);
+        assert_eq!(a.compare(&set((0..100).rev()).digest()), None);
+        assert_eq!(set([]).compare(&AntiEntropy::new().digest()), None);
+    }
+
+    #[test]
+    fn an_empty_side_is_filled() {
+        let mut a = set([]);
+        let mut b = set(0..50);
+        exchange(&mut a, &mut b);
+        assert_eq!(a.values(), b.values());
+
+        let mut a = set(0..50);
+        let mut b = set([]);
+        exchange(&mut a, &mut b);
+        assert_eq!(a.values(), b.values());
+    }
+}
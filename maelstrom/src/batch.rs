@@ -0,0 +1,190 @@
+use std::{any::Any, collections::BTreeMap};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::MaelstromError;
+
+// A peer payload that later payloads to the same destination can be merged
+// into, such as a set of values to gossip.
+pub trait Coalesce: Sized {
+    // Merges `next` into this payload, or hands it back when the two cannot
+    // be combined.
+    fn coalesce(&mut self, next: Self) -> Option<Self>;
+}
+
+struct Pending {
+    payload: Box<dyn Any>,
+    serialize: fn(&dyn Any) -> Result<Value, MaelstromError>,
+    // How many payloads have been merged into this one.
+    merged: usize,
+}
+
+fn serialize<P: Serialize + 'static>(payload: &dyn Any) -> Result<Value, MaelstromError> {
+    let payload = payload.downcast_ref::<P>().ok_or(MaelstromError::IOError)?;
+
+    serde_json::to_value(payload).map_err(|_| MaelstromError::IOError)
+}
+
+// Outbound payloads held back per destination. Each destination has at most
+// one pending payload that later ones are coalesced into; one that cannot be
+// merged sends the pending payload first, so payloads leave in order.
+pub(crate) struct Batches {
+    max_batch: usize,
+    pending: BTreeMap<String, Pending>,
+}
+
+impl Batches {
+    pub fn new(max_batch: usize) -> Self {
+        Self {
+            max_batch: max_batch.max(1),
+            pending: BTreeMap::new(),
+        }
+    }
+
+    // Buffers `payload` for `dest`, returning whatever is to be sent now: a
+    // pending payload `payload` could not be merged into, or the batch once it
+    // holds `max_batch` payloads.
+    pub fn push<P>(&mut self, dest: &str, payload: P) -> Result<Vec<Value>, MaelstromError>
+    where
+        P: Coalesce + Serialize + 'static,
+    {
+        let mut ready = vec![];
+        let mut payload = Some(payload);
+        if let Some(pending) = self.pending.get_mut(dest) {
+            if let Some(current) = pending.payload.downcast_mut::<P>() {
+                payload = current.coalesce(payload.take().expect("payload is set"));
+                if payload.is_none() {
+                    pending.merged += 1;
+                }
+            }
+        }
+
+        if let Some(payload) = payload {
+            if let Some(pending) = self.pending.remove(dest) {
+                ready.push((pending.serialize)(pending.payload.as_ref())?);
+            }
+
+            self.pending.insert(
+                dest.to_string(),
+                Pending {
+                    payload: Box::new(payload),
+                    serialize: serialize::<P>,
+                    merged: 1,
+                },
+            );
+        }
+
+        if self
+            .pending
+            .get(dest)
+            .is_some_and(|pending| pending.merged >= self.max_batch)
+        {
+            let pending = self.pending.remove(dest).expect("pending is present");
+            ready.push((pending.serialize)(pending.payload.as_ref())?);
+        }
+
+        Ok(ready)
+    }
+
+    // Every pending payload, by destination.
+    pub fn flush(&mut self) -> Result<Vec<(String, Value)>, MaelstromError> {
+        std::mem::take(&mut self.pending)
+            .into_iter()
+            .map(|(dest, pending)| Ok((dest, (pending.serialize)(pending.payload.as_ref())?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Serialize)]
+    #[serde(tag = "type", rename_all = "snake_case")]
+    enum Payload {
+        Values { values: Vec<usize> },
+        Hello,
+    }
+
+    impl Coalesce for Payload {
+        fn coalesce(&mut self, next: Self) -> Option<Self> {
+            match (self, next) {
+                (Self::Values { values }, Self::Values { values: next }) => {
+                    values.extend(next);
+                    None
+                }
+                (_, next) => Some(next),
+            }
+        }
+    }
+
+    fn values(values: &[usize]) -> Payload {
+        Payload::Values {
+            values: values.to_vec(),
+        }
+    }
+
+    #[test]
+    fn coalesces_payloads_to_the_same_destination() {
+        let mut batches = Batches::new(64);
+        assert!(batches.push("n1", values(&[1])).unwrap().is_empty());
+        assert!(batches.push("n1", values(&[2, 3])).unwrap().is_empty());
+        assert!(batches.push("n2", values(&[4])).unwrap().is_empty());
+
+        assert_eq!(
+            batches.flush().unwrap(),
+            vec![
+                (
+                    "n1".to_string(),
+                    json!({ "type": "values", "values": [1, 2, 3] })
+                ),
+                ("n2".to_string(), json!({ "type": "values", "values": [4] })),
+            ]
+        );
+        assert!(batches.flush().unwrap().is_empty());
+    }
+
+    #[test]
+    fn sends_the_pending_payload_before_one_that_cannot_merge() {
+        let mut batches = Batches::new(64);
+        batches.push("n1", values(&[1])).unwrap();
+        assert_eq!(
+            batches.push("n1", Payload::Hello).unwrap(),
+            vec![json!({ "type": "values", "values": [1] })]
+        );
+
+        // Nothing merges into the hello either, so it goes out next.
+        assert_eq!(
+            batches.push("n1", values(&[2])).unwrap(),
+            vec![json!({ "type": "hello" })]
+        );
+        assert_eq!(
+            batches.flush().unwrap(),
+            vec![("n1".to_string(), json!({ "type": "values", "values": [2] }))]
+        );
+    }
+
+    #[test]
+    fn sends_a_batch_once_it_is_full() {
+        let mut batches = Batches::new(3);
+        assert!(batches.push("n1", values(&[1])).unwrap().is_empty());
+        assert!(batches.push("n1", values(&[2])).unwrap().is_empty());
+        assert_eq!(
+            batches.push("n1", values(&[3])).unwrap(),
+            vec![json!({ "type": "values", "values": [1, 2, 3] })]
+        );
+        assert!(batches.flush().unwrap().is_empty());
+    }
+
+    #[test]
+    fn a_batch_of_one_sends_straight_away() {
+        let mut batches = Batches::new(0);
+        assert_eq!(
+            batches.push("n1", values(&[1])).unwrap(),
+            vec![json!({ "type": "values", "values": [1] })]
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Debug, Display},
+    str::FromStr,
+    time::Duration,
+};
+
+use crate::error::MaelstromError;
+
+// A node's typed configuration, loaded once at startup before `new`.
+pub trait NodeConfig: Debug + Sized {
+    // Environment variables are named after the setting under this prefix:
+    // `interval-ms` with prefix `BROADCAST` is `BROADCAST_INTERVAL_MS`.
+    const PREFIX: &'static str;
+
+    fn load(settings: &mut Settings) -> Result<Self, MaelstromError>;
+}
+
+impl NodeConfig for () {
+    const PREFIX: &'static str = "";
+
+    fn load(_: &mut Settings) -> Result<Self, MaelstromError> {
+        Ok(())
+    }
+}
+
+// Raw settings from the command line and the environment. A setting given as
+// `--name value` or `--name=value` wins over its environment variable; one
+// given in neither takes its default.
+pub struct Settings {
+    prefix: String,
+    args: HashMap<String, String>,
+    env: HashMap<String, String>,
+    read: HashSet<String>,
+}
+
+impl Settings {
+    pub fn new(
+        prefix: &str,
+        args: impl IntoIterator<Item = String>,
+        env: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, MaelstromError> {
+        let mut parsed = HashMap::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let Some(flag) = arg.strip_prefix("--") else {
+                return Err(MaelstromError::ConfigError(format!(
+                    "unexpected argument {arg:?}"
+                )));
+            };
+
+            let (name, value) = match flag.split_once('=') {
+                Some((name, value)) => (name.to_string(), value.to_string()),
+                None => {
+                    let value = args.next().ok_or_else(|| {
+                        MaelstromError::ConfigError(format!("--{flag} expects a value"))
+                    })?;
+
+                    (flag.to_string(), value)
+                }
+            };
+
+            parsed.insert(name, value);
+        }
+
+        Ok(Self {
+            prefix: prefix.to_string(),
+            args: parsed,
+            env: env.into_iter().collect(),
+            read: HashSet::new(),
+        })
+    }
+
+    pub fn from_process(prefix: &str) -> Result<Self, MaelstromError> {
+        Self::new(prefix, std::env::args().skip(1), std::env::vars())
+    }
+
+    fn var(&self, name: &str) -> String {
+        format!("{}_{}", self.prefix, name.to_uppercase().replace('-', "_"))
+    }
+
+    fn raw(&mut self, name: &str) -> Option<(String, String)> {
+        self.read.insert(name.to_string());
+        if let Some(value) = self.args.get(name) {
+            return Some((format!("--{name}"), value.clone()));
+        }
+
+        let var = self.var(name);
+        self.env.get(&var).map(|value| (var, value.clone()))
+    }
+
+    // Reads a setting with `parse`, which returns `None` for invalid values;
+    // `expected` describes the valid ones.
+    pub fn parse<T>(
+        &mut self,
+        name: &str,
+        default: T,
+        expected: &str,
+        parse: impl FnOnce(&str) -> Option<T>,
+    ) -> Result<T, MaelstromError> {
+        let Some((source, value)) = self.raw(name) else {
+            return Ok(default);
+        };
+
+        parse(&value).ok_or_else(|| {
+            MaelstromError::ConfigError(format!("{source} is {value:?}, expected {expected}"))
+        })
+    }
+
+    pub fn number<T>(&mut self, name: &str, default: T, min: T, max: T) -> Result<T, MaelstromError>
+    where
+        T: FromStr + PartialOrd + Display,
+    {
+        let expected = format!("a number from {min} to {max}");
+        self.parse(name, default, &expected, |value| {
+            value
+                .parse()
+                .ok()
+                .filter(|value: &T| *value >= min && *value <= max)
+        })
+    }
+
+    pub fn millis(
+        &mut self,
+        name: &str,
+        default: Duration,
+        min: Duration,
+        max: Duration,
+    ) -> Result<Duration, MaelstromError> {
+        let millis = |duration: Duration| duration.as_millis() as u64;
+        self.number(name, millis(default), millis(min), millis(max))
+            .map(Duration::from_millis)
+    }
+
+    // Fails on command line flags no setting was read under, which are
+    // most likely typos.
+    pub fn finish(&self) -> Result<(), MaelstromError> {
+        let mut unknown = self
+            .args
+            .keys()
+            .filter(|name| !self.read.contains(*name))
+            .map(|name| format!("--{name}"))
+            .collect::<Vec<_>>();
+
+        if unknown.is_empty() {
+            return Ok(());
+        }
+
+        unknown.sort();
+        Err(MaelstromError::ConfigError(format!(
+            "unknown arguments {}",
+            unknown.join(", ")
+        )))
+    }
+}
+
+// Loads `C` from this process's arguments and environment, logging the
+// effective configuration to stderr.
+pub fn load<C: NodeConfig>() -> Result<C, MaelstromError> {
+    let mut settings = Settings::from_process(C::PREFIX)?;
+    let config = C::load(&mut settings)?;
+    settings.finish()?;
+    eprintln!("config: {config:?}");
+    Ok(config)
+}
@@ -0,0 +1,669 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    hash::Hash,
+};
+
+use serde::{Deserialize, Serialize};
+
+// A state-based CRDT. `merge` is the join of two states: commutative,
+// associative and idempotent, so replicas converge however often and in
+// whatever order states are exchanged. Deltas are states too: every mutator
+// returns the delta it produced, and `delta` extracts what `since` is missing,
+// so merging a delta into a replica that has seen `since` brings it up to
+// this state.
+pub trait Crdt: Clone + Default {
+    fn merge(&mut self, other: &Self);
+
+    fn delta(&self, since: &Self) -> Self;
+}
+
+// Maps with keys that are not strings are sent as lists of pairs, since JSON
+// object keys are always strings.
+mod pairs {
+    use std::{collections::HashMap, hash::Hash};
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize,
+        V: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_seq(map)
+    }
+
+    pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<HashMap<K, V>, D::Error>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<(K, V)>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
+
+// A grow-only set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(deserialize = "T: Deserialize<'de> + Eq + Hash"))]
+pub struct GSet<T: Eq + Hash> {
+    elements: HashSet<T>,
+}
+
+impl<T: Eq + Hash> Default for GSet<T> {
+    fn default() -> Self {
+        Self {
+            elements: HashSet::new(),
+        }
+    }
+}
+
+impl<T: Clone + Eq + Hash> GSet<T> {
+    pub fn insert(&mut self, element: T) -> Self {
+        self.elements.insert(element.clone());
+        Self {
+            elements: HashSet::from([element]),
+        }
+    }
+
+    pub fn contains(&self, element: &T) -> bool {
+        self.elements.contains(element)
+    }
+
+    pub fn elements(&self) -> &HashSet<T> {
+        &self.elements
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+}
+
+impl<T: Eq + Hash> FromIterator<T> for GSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(elements: I) -> Self {
+        Self {
+            elements: elements.into_iter().collect(),
+        }
+    }
+}
+
+impl<'a, T: Eq + Hash> IntoIterator for &'a GSet<T> {
+    type Item = &'a T;
+    type IntoIter = std::collections::hash_set::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.elements.iter()
+    }
+}
+
+impl<T: Clone + Eq + Hash> Crdt for GSet<T> {
+    fn merge(&mut self, other: &Self) {
+        self.elements.extend(other.elements.iter().cloned());
+    }
+
+    fn delta(&self, since: &Self) -> Self {
+        Self {
+            elements: self.elements.difference(&since.elements).cloned().collect(),
+        }
+    }
+}
+
+// A set whose elements can be removed once and never added back: removals
+// are kept in a second grow-only set of tombstones.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(deserialize = "T: Deserialize<'de> + Eq + Hash"))]
+pub struct TwoPSet<T: Eq + Hash> {
+    added: GSet<T>,
+    removed: GSet<T>,
+}
+
+impl<T: Eq + Hash> Default for TwoPSet<T> {
+    fn default() -> Self {
+        Self {
+            added: GSet::default(),
+            removed: GSet::default(),
+        }
+    }
+}
+
+impl<T: Clone + Eq + Hash> TwoPSet<T> {
+    pub fn insert(&mut self, element: T) -> Self {
+        Self {
+            added: self.added.insert(element),
+            removed: GSet::default(),
+        }
+    }
+
+    // Only elements in the set can be removed; otherwise there is no delta.
+    pub fn remove(&mut self, element: T) -> Option<Self> {
+        if !self.contains(&element) {
+            return None;
+        }
+
+        Some(Self {
+            added: GSet::default(),
+            removed: self.removed.insert(element),
+        })
+    }
+
+    pub fn contains(&self, element: &T) -> bool {
+        self.added.contains(element) && !self.removed.contains(element)
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = &T> {
+        self.added
+            .elements()
+            .iter()
+            .filter(|element| !self.removed.contains(element))
+    }
+}
+
+impl<T: Clone + Eq + Hash> Crdt for TwoPSet<T> {
+    fn merge(&mut self, other: &Self) {
+        self.added.merge(&other.added);
+        self.removed.merge(&other.removed);
+    }
+
+    fn delta(&self, since: &Self) -> Self {
+        Self {
+            added: self.added.delta(&since.added),
+            removed: self.removed.delta(&since.removed),
+        }
+    }
+}
+
+// Identifies one insertion: the replica that made it and its count of
+// insertions so far.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Dot {
+    pub replica: String,
+    pub counter: u64,
+}
+
+// An observed-remove set. Every insertion is tagged with a fresh dot and a
+// removal tombstones only the dots it has seen, so an insertion concurrent
+// with a removal survives it. Tombstones are never collected.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T: Serialize",
+    deserialize = "T: Deserialize<'de> + Eq + Hash"
+))]
+pub struct OrSet<T: Eq + Hash> {
+    #[serde(with = "pairs")]
+    dots: HashMap<T, HashSet<Dot>>,
+    removed: HashSet<Dot>,
+    counters: BTreeMap<String, u64>,
+}
+
+impl<T: Eq + Hash> Default for OrSet<T> {
+    fn default() -> Self {
+        Self {
+            dots: HashMap::new(),
+            removed: HashSet::new(),
+            counters: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T: Clone + Eq + Hash> OrSet<T> {
+    pub fn insert(&mut self, replica: &str, element: T) -> Self {
+        let counter = self.counters.entry(replica.to_string()).or_default();
+        *counter += 1;
+        let dot = Dot {
+            replica: replica.to_string(),
+            counter: *counter,
+        };
+
+        self.dots
+            .entry(element.clone())
+            .or_default()
+            .insert(dot.clone());
+        Self {
+            dots: HashMap::from([(element, HashSet::from([dot]))]),
+            removed: HashSet::new(),
+            counters: BTreeMap::from([(replica.to_string(), *counter)]),
+        }
+    }
+
+    pub fn remove(&mut self, element: &T) -> Self {
+        let live = self.live_dots(element).cloned().collect::<HashSet<_>>();
+        self.removed.extend(live.iter().cloned());
+        Self {
+            removed: live,
+            ..Self::default()
+        }
+    }
+
+    pub fn contains(&self, element: &T) -> bool {
+        self.live_dots(element).next().is_some()
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = &T> {
+        self.dots.keys().filter(|element| self.contains(element))
+    }
+
+    fn live_dots<'a>(&'a self, element: &T) -> impl Iterator<Item = &'a Dot> {
+        self.dots
+            .get(element)
+            .into_iter()
+            .flatten()
+            .filter(|dot| !self.removed.contains(dot))
+    }
+}
+
+impl<T: Clone + Eq + Hash> Crdt for OrSet<T> {
+    fn merge(&mut self, other: &Self) {
+        for (element, dots) in &other.dots {
+            self.dots
+                .entry(element.clone())
+                .or_default()
+                .extend(dots.iter().cloned());
+        }
+
+        self.removed.extend(other.removed.iter().cloned());
+        for (replica, &counter) in &other.counters {
+            let entry = self.counters.entry(replica.clone()).or_default();
+            *entry = (*entry).max(counter);
+        }
+    }
+
+    fn delta(&self, since: &Self) -> Self {
+        let dots = self
+            .dots
+            .iter()
+            .filter_map(|(element, dots)| {
+                let known = since.dots.get(element);
+                let missing = dots
+                    .iter()
+                    .filter(|dot| known.is_none_or(|known| !known.contains(dot)))
+                    .cloned()
+                    .collect::<HashSet<_>>();
+
+                (!missing.is_empty()).then(|| (element.clone(), missing))
+            })
+            .collect();
+
+        Self {
+            dots,
+            removed: self.removed.difference(&since.removed).cloned().collect(),
+            counters: self
+                .counters
+                .iter()
+                .filter(|(replica, &counter)| since.counters.get(*replica) < Some(&counter))
+                .map(|(replica, &counter)| (replica.clone(), counter))
+                .collect(),
+        }
+    }
+}
+
+// A grow-only counter: each replica only ever raises its own count, so
+// merging takes the highest count seen per replica.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GCounter {
+    counts: HashMap<String, u64>,
+}
+
+impl GCounter {
+    pub fn increment(&mut self, replica: &str, amount: u64) -> Self {
+        let count = self.counts.entry(replica.to_string()).or_default();
+        *count += amount;
+        Self {
+            counts: HashMap::from([(replica.to_string(), *count)]),
+        }
+    }
+
+    pub fn value(&self) -> u64 {
+        self.counts.values().sum()
+    }
+}
+
+impl Crdt for GCounter {
+    fn merge(&mut self, other: &Self) {
+        for (replica, &count) in &other.counts {
+            let entry = self.counts.entry(replica.clone()).or_default();
+            *entry = (*entry).max(count);
+        }
+    }
+
+    fn delta(&self, since: &Self) -> Self {
+        Self {
+            counts: self
+                .counts
+                .iter()
+                .filter(|(replica, &count)| since.counts.get(*replica) < Some(&count))
+                .map(|(replica, &count)| (replica.clone(), count))
+                .collect(),
+        }
+    }
+}
+
+// A counter that also goes down, as a pair of grow-only counters.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PnCounter {
+    increments: GCounter,
+    decrements: GCounter,
+}
+
+impl PnCounter {
+    pub fn add(&mut self, replica: &str, delta: i64) -> Self {
+        match delta < 0 {
+            true => Self {
+                increments: GCounter::default(),
+                decrements: self.decrements.increment(replica, delta.unsigned_abs()),
+            },
+            false => Self {
+                increments: self.increments.increment(replica, delta.unsigned_abs()),
+                decrements: GCounter::default(),
+            },
+        }
+    }
+
+    pub fn value(&self) -> i64 {
+        self.increments.value() as i64 - self.decrements.value() as i64
+    }
+}
+
+impl Crdt for PnCounter {
+    fn merge(&mut self, other: &Self) {
+        self.increments.merge(&other.increments);
+        self.decrements.merge(&other.decrements);
+    }
+
+    fn delta(&self, since: &Self) -> Self {
+        Self {
+            increments: self.increments.delta(&since.increments),
+            decrements: self.decrements.delta(&since.decrements),
+        }
+    }
+}
+
+// Orders writes to a register: a Lamport clock, ties broken by replica.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Timestamp {
+    pub clock: u64,
+    pub replica: String,
+}
+
+// A last-writer-wins register. Each write is stamped after every write the
+// replica has seen, and merging keeps the write with the latest stamp.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LwwRegister<T> {
+    entry: Option<(Timestamp, T)>,
+}
+
+impl<T> Default for LwwRegister<T> {
+    fn default() -> Self {
+        Self { entry: None }
+    }
+}
+
+impl<T: Clone> LwwRegister<T> {
+    pub fn set(&mut self, replica: &str, value: T) -> Self {
+        let clock = self.entry.as_ref().map_or(0, |(stamp, _)| stamp.clock) + 1;
+        let stamp = Timestamp {
+            clock,
+            replica: replica.to_string(),
+        };
+
+        self.entry = Some((stamp, value));
+        self.clone()
+    }
+
+    pub fn get(&self) -> Option<&T> {
+        self.entry.as_ref().map(|(_, value)| value)
+    }
+
+    fn stamp(&self) -> Option<&Timestamp> {
+        self.entry.as_ref().map(|(stamp, _)| stamp)
+    }
+}
+
+impl<T: Clone> Crdt for LwwRegister<T> {
+    fn merge(&mut self, other: &Self) {
+        if other.stamp() > self.stamp() {
+            self.entry = other.entry.clone();
+        }
+    }
+
+    fn delta(&self, since: &Self) -> Self {
+        match self.stamp() > since.stamp() {
+            true => self.clone(),
+            false => Self::default(),
+        }
+    }
+}
+
+// Counts, per replica, the writes a value has seen.
+pub type VersionVector = BTreeMap<String, u64>;
+
+fn dominates(ours: &VersionVector, theirs: &VersionVector) -> bool {
+    ours != theirs
+        && theirs
+            .iter()
+            .all(|(replica, count)| ours.get(replica).is_some_and(|ours| ours >= count))
+}
+
+// A multi-value register. A write replaces every value the replica has seen,
+// but concurrent writes are all kept until a later write supersedes them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MvRegister<T> {
+    entries: Vec<(VersionVector, T)>,
+}
+
+impl<T> Default for MvRegister<T> {
+    fn default() -> Self {
+        Self { entries: vec![] }
+    }
+}
+
+impl<T: Clone> MvRegister<T> {
+    pub fn set(&mut self, replica: &str, value: T) -> Self {
+        let mut version = VersionVector::new();
+        for (seen, _) in &self.entries {
+            for (replica, &count) in seen {
+                let entry = version.entry(replica.clone()).or_default();
+                *entry = (*entry).max(count);
+            }
+        }
+
+        *version.entry(replica.to_string()).or_default() += 1;
+        self.entries = vec![(version, value)];
+        self.clone()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+}
+
+impl<T: Clone> Crdt for MvRegister<T> {
+    fn merge(&mut self, other: &Self) {
+        let mut entries = self.entries.clone();
+        for (version, value) in &other.entries {
+            if !entries.iter().any(|(seen, _)| seen == version) {
+                entries.push((version.clone(), value.clone()));
+            }
+        }
+
+        self.entries = entries
+            .iter()
+            .filter(|(version, _)| !entries.iter().any(|(other, _)| dominates(other, version)))
+            .cloned()
+            .collect();
+    }
+
+    fn delta(&self, since: &Self) -> Self {
+        Self {
+            entries: self
+                .entries
+                .iter()
+                .filter(|(version, _)| !since.entries.iter().any(|(seen, _)| seen == version))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+
+    use super::*;
+
+    fn merged<C: Crdt>(state: &C, other: &C) -> C {
+        let mut state = state.clone();
+        state.merge(other);
+        state
+    }
+
+    // Checks that merge is commutative, associative and idempotent over every
+    // combination of `states`, and that a delta brings a replica that has
+    // seen `since` up to date. `same` compares states whose representation
+    // may differ in order only.
+    fn obeys_the_laws<C: Crdt + Debug>(states: &[C], same: impl Fn(&C, &C) -> bool) {
+        for a in states {
+            assert!(same(&merged(a, a), a), "merge is not idempotent for {a:?}");
+            for b in states {
+                let (ab, ba) = (merged(a, b), merged(b, a));
+                assert!(same(&ab, &ba), "merge is not commutative for {a:?}, {b:?}");
+                assert!(
+                    same(&merged(b, &ab.delta(b)), &ab),
+                    "delta of {ab:?} since {b:?} does not catch up"
+                );
+
+                for c in states {
+                    assert!(
+                        same(&merged(&ab, c), &merged(a, &merged(b, c))),
+                        "merge is not associative for {a:?}, {b:?}, {c:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn g_set() {
+        let mut a = GSet::default();
+        a.insert(1);
+        let mut b = a.clone();
+        b.insert(2);
+        let mut c = GSet::default();
+        c.insert(3);
+
+        obeys_the_laws(&[GSet::default(), a, b, c], PartialEq::eq);
+    }
+
+    #[test]
+    fn two_p_set() {
+        let mut a = TwoPSet::default();
+        a.insert(1);
+        a.insert(2);
+        let mut b = a.clone();
+        b.remove(1);
+        let mut c = a.clone();
+        c.insert(3);
+        c.remove(2);
+
+        obeys_the_laws(
+            &[TwoPSet::default(), a, b.clone(), c.clone()],
+            PartialEq::eq,
+        );
+        assert_eq!(merged(&b, &c).elements().collect::<Vec<_>>(), vec![&3]);
+    }
+
+    #[test]
+    fn or_set_keeps_concurrent_insertions() {
+        let mut a = OrSet::default();
+        a.insert("n0", 1);
+        let mut b = a.clone();
+        b.remove(&1);
+        let mut c = a.clone();
+        c.insert("n2", 1);
+
+        obeys_the_laws(&[OrSet::default(), a, b.clone(), c.clone()], PartialEq::eq);
+        assert!(merged(&b, &c).contains(&1));
+        assert!(!merged(&b, &OrSet::default()).contains(&1));
+    }
+
+    #[test]
+    fn counters() {
+        let mut a = GCounter::default();
+        a.increment("n0", 2);
+        let mut b = a.clone();
+        b.increment("n1", 3);
+        let mut c = a.clone();
+        c.increment("n0", 1);
+
+        obeys_the_laws(
+            &[GCounter::default(), a, b.clone(), c.clone()],
+            PartialEq::eq,
+        );
+        assert_eq!(merged(&b, &c).value(), 6);
+
+        let mut a = PnCounter::default();
+        a.add("n0", 5);
+        let mut b = a.clone();
+        b.add("n1", -7);
+        let mut c = a.clone();
+        c.add("n0", -1);
+
+        obeys_the_laws(
+            &[PnCounter::default(), a, b.clone(), c.clone()],
+            PartialEq::eq,
+        );
+        assert_eq!(merged(&b, &c).value(), -3);
+    }
+
+    #[test]
+    fn lww_register_keeps_the_latest_write() {
+        let mut a = LwwRegister::default();
+        a.set("n0", "a");
+        let mut b = a.clone();
+        b.set("n1", "b");
+        let mut c = a.clone();
+        c.set("n2", "c");
+
+        obeys_the_laws(
+            &[LwwRegister::default(), a, b.clone(), c.clone()],
+            PartialEq::eq,
+        );
+        assert_eq!(merged(&b, &c).get(), Some(&"c"));
+    }
+
+    #[test]
+    fn mv_register_keeps_concurrent_writes() {
+        let mut a = MvRegister::default();
+        a.set("n0", "a");
+        let mut b = a.clone();
+        b.set("n1", "b");
+        let mut c = a.clone();
+        c.set("n2", "c");
+        let mut d = merged(&b, &c);
+        d.set("n0", "d");
+
+        // Concurrent values are kept in the order they were merged in.
+        let same = |x: &MvRegister<&str>, y: &MvRegister<&str>| {
+            let mut x = x.entries.clone();
+            let mut y = y.entries.clone();
+            x.sort();
+            y.sort();
+            x == y
+        };
+
+        obeys_the_laws(
+            &[MvRegister::default(), a, b.clone(), c.clone(), d.clone()],
+            same,
+        );
+
+        let mut values = merged(&b, &c).values().copied().collect::<Vec<_>>();
+        values.sort();
+        assert_eq!(values, vec!["b", "c"]);
+        assert_eq!(merged(&b, &d).values().collect::<Vec<_>>(), vec![&"d"]);
+    }
+}
@@ -0,0 +1,373 @@
+use std::ops::Deref;
+
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+// How a set of integers is written in a JSON body. Every encoding is plain
+// JSON, so Maelstrom relays it like any other message.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Encoding {
+    // An array of the values, `[1, 2, 3, 7]`.
+    #[default]
+    Plain,
+    // Sorted runs of consecutive values as start and length pairs,
+    // `{"intervals": [1, 3, 7, 1]}`.
+    Intervals,
+    // The sorted values as differences from the one before,
+    // `{"deltas": [1, 1, 1, 4]}`.
+    Deltas,
+}
+
+impl Encoding {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "plain" => Some(Self::Plain),
+            "intervals" => Some(Self::Intervals),
+            "deltas" => Some(Self::Deltas),
+            _ => None,
+        }
+    }
+
+    // The first of `ours` that `theirs` supports. Every node reads plain
+    // arrays, so that is what is left when nothing else is shared.
+    pub fn negotiate(ours: &[Encoding], theirs: &[Encoding]) -> Self {
+        ours.iter()
+            .find(|encoding| theirs.contains(encoding))
+            .copied()
+            .unwrap_or(Self::Plain)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Wire {
+    Plain(Vec<usize>),
+    Intervals { intervals: Vec<usize> },
+    Deltas { deltas: Vec<usize> },
+}
+
+impl Wire {
+    fn encode(values: impl IntoIterator<Item = usize>, encoding: Encoding) -> Self {
+        let mut values = values.into_iter().collect::<Vec<_>>();
+        values.sort_unstable();
+        values.dedup();
+        match encoding {
+            Encoding::Plain => Self::Plain(values),
+            Encoding::Intervals => {
+                let mut intervals: Vec<usize> = vec![];
+                for value in values {
+                    match intervals.len() {
+                        len if len >= 2 && intervals[len - 2] + intervals[len - 1] == value => {
+                            intervals[len - 1] += 1;
+                        }
+                        _ => intervals.extend([value, 1]),
+                    }
+                }
+
+                Self::Intervals { intervals }
+            }
+            Encoding::Deltas => {
+                let mut previous = 0;
+                let deltas = values
+                    .into_iter()
+                    .map(|value| {
+                        let delta = value - previous;
+                        previous = value;
+                        delta
+                    })
+                    .collect();
+
+                Self::Deltas { deltas }
+            }
+        }
+    }
+
+    fn encoding(&self) -> Encoding {
+        match self {
+            Self::Plain(_) => Encoding::Plain,
+            Self::Intervals { .. } => Encoding::Intervals,
+            Self::Deltas { .. } => Encoding::Deltas,
+        }
+    }
+
+    // Fails on input no encoder writes: a start without a length, or values
+    // past the largest integer.
+    fn decode<C: FromIterator<usize>>(self) -> Result<C, &'static str> {
+        match self {
+            Self::Plain(values) => Ok(values.into_iter().collect()),
+            Self::Intervals { intervals } => {
+                if intervals.len() % 2 != 0 {
+                    return Err("intervals must be start and length pairs");
+                }
+
+                let runs = intervals
+                    .chunks_exact(2)
+                    .map(|run| match run[0].checked_add(run[1]) {
+                        Some(end) => Ok(run[0]..end),
+                        None => Err("an interval runs past the largest integer"),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok(runs.into_iter().flatten().collect())
+            }
+            Self::Deltas { deltas } => {
+                let mut value = 0_usize;
+                deltas
+                    .into_iter()
+                    .map(|delta| {
+                        value = value
+                            .checked_add(delta)
+                            .ok_or("deltas add up past the largest integer")?;
+                        Ok(value)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+// A set of integers sent in a chosen encoding. It reads any encoding, and
+// remembers which one it came in.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Encoded<C> {
+    pub encoding: Encoding,
+    pub values: C,
+}
+
+impl<C> Encoded<C> {
+    pub fn new(values: C, encoding: Encoding) -> Self {
+        Self { encoding, values }
+    }
+}
+
+impl<C> Deref for Encoded<C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        &self.values
+    }
+}
+
+impl<C> Serialize for Encoded<C>
+where
+    for<'a> &'a C: IntoIterator<Item = &'a usize>,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_as(&self.values, self.encoding, serializer)
+    }
+}
+
+impl<'de, C: FromIterator<usize>> Deserialize<'de> for Encoded<C> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let wire = Wire::deserialize(deserializer)?;
+        Ok(Self {
+            encoding: wire.encoding(),
+            values: wire.decode().map_err(D::Error::custom)?,
+        })
+    }
+}
+
+fn serialize_as<C, S>(values: &C, encoding: Encoding, serializer: S) -> Result<S::Ok, S::Error>
+where
+    for<'a> &'a C: IntoIterator<Item = &'a usize>,
+    S: Serializer,
+{
+    Wire::encode(values.into_iter().copied(), encoding).serialize(serializer)
+}
+
+fn deserialize_any<'de, C, D>(deserializer: D) -> Result<C, D::Error>
+where
+    C: FromIterator<usize>,
+    D: Deserializer<'de>,
+{
+    Wire::deserialize(deserializer)?
+        .decode()
+        .map_err(D::Error::custom)
+}
+
+// Serde adapters for integer set fields that always use one encoding, as in
+// `#[serde(with = "maelstrom::encoding::intervals")]`. Like `Encoded`, they
+// read any encoding.
+pub mod intervals {
+    use serde::{Deserializer, Serializer};
+
+    use super::Encoding;
+
+    pub fn serialize<C, S>(values: &C, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        for<'a> &'a C: IntoIterator<Item = &'a usize>,
+        S: Serializer,
+    {
+        super::serialize_as(values, Encoding::Intervals, serializer)
+    }
+
+    pub fn deserialize<'de, C, D>(deserializer: D) -> Result<C, D::Error>
+    where
+        C: FromIterator<usize>,
+        D: Deserializer<'de>,
+    {
+        super::deserialize_any(deserializer)
+    }
+}
+
+pub mod deltas {
+    use serde::{Deserializer, Serializer};
+
+    use super::Encoding;
+
+    pub fn serialize<C, S>(values: &C, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        for<'a> &'a C: IntoIterator<Item = &'a usize>,
+        S: Serializer,
+    {
+        super::serialize_as(values, Encoding::Deltas, serializer)
+    }
+
+    pub fn deserialize<'de, C, D>(deserializer: D) -> Result<C, D::Error>
+    where
+        C: FromIterator<usize>,
+        D: Deserializer<'de>,
+    {
+        super::deserialize_any(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashSet};
+
+    use super::*;
+
+    const ENCODINGS: [Encoding; 3] = [Encoding::Plain, Encoding::Intervals, Encoding::Deltas];
+
+    fn round_trip(values: &[usize], encoding: Encoding) -> Encoded<HashSet<usize>> {
+        let encoded = Encoded::new(values.iter().copied().collect::<HashSet<_>>(), encoding);
+        let json = serde_json::to_string(&encoded).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn every_encoding_round_trips() {
+        let sets: [&[usize]; 5] = [&[], &[0], &[7], &[1, 2, 3, 7, 8, 20], &[0, 1000, 1001, 5]];
+        for encoding in ENCODINGS {
+            for values in sets {
+                let decoded = round_trip(values, encoding);
+                assert_eq!(decoded.encoding, encoding, "{values:?}");
+                assert_eq!(
+                    decoded.values,
+                    values.iter().copied().collect::<HashSet<_>>(),
+                    "{encoding:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn writes_the_documented_forms() {
+        let values = [1, 2, 3, 7];
+        let json = |encoding| serde_json::to_value(Encoded::new(values, encoding)).unwrap();
+        assert_eq!(json(Encoding::Plain), serde_json::json!([1, 2, 3, 7]));
+        assert_eq!(
+            json(Encoding::Intervals),
+            serde_json::json!({ "intervals": [1, 3, 7, 1] })
+        );
+        assert_eq!(
+            json(Encoding::Deltas),
+            serde_json::json!({ "deltas": [1, 1, 1, 4] })
+        );
+    }
+
+    #[test]
+    fn empty_sets_keep_their_encoding() {
+        for encoding in ENCODINGS {
+            let decoded = round_trip(&[], encoding);
+            assert_eq!(decoded.encoding, encoding);
+            assert!(decoded.values.is_empty());
+        }
+    }
+
+    #[test]
+    fn adapters_read_any_encoding() {
+        #[derive(Serialize, Deserialize)]
+        struct Intervals {
+            #[serde(with = "crate::encoding::intervals")]
+            values: BTreeSet<usize>,
+        }
+
+        let values = BTreeSet::from([3, 4, 5, 9]);
+        for encoding in ENCODINGS {
+            let json = serde_json::json!({ "values": Encoded::new(values.clone(), encoding) });
+            let decoded: Intervals = serde_json::from_value(json).unwrap();
+            assert_eq!(decoded.values, values);
+        }
+
+        let json = serde_json::to_value(Intervals {
+            values: values.clone(),
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "values": { "intervals": [3, 3, 9, 1] } })
+        );
+    }
+
+    #[test]
+    fn negotiate_picks_our_first_shared_encoding() {
+        use Encoding::*;
+
+        assert_eq!(
+            Encoding::negotiate(&[Intervals, Deltas, Plain], &[Deltas, Intervals]),
+            Intervals
+        );
+        assert_eq!(
+            Encoding::negotiate(&[Deltas, Intervals], &[Intervals, Deltas]),
+            Deltas
+        );
+        assert_eq!(Encoding::negotiate(&[Intervals], &[Deltas]), Plain);
+        assert_eq!(Encoding::negotiate(&[], &[Intervals]), Plain);
+        assert_eq!(Encoding::negotiate(&[Intervals, Deltas], &[]), Plain);
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let max = usize::MAX;
+        let malformed = [
+            serde_json::json!({ "intervals": [1, 3, 7] }),
+            serde_json::json!({ "intervals": [max, 2] }),
+            serde_json::json!({ "deltas": [max, 1] }),
+            serde_json::json!({ "values": [1] }),
+            serde_json::json!([-1]),
+        ];
+
+        for json in malformed {
+            let encoded = serde_json::from_value::<Encoded<Vec<usize>>>(json.clone());
+            assert!(encoded.is_err(), "{json} should not decode");
+        }
+
+        let error =
+            serde_json::from_value::<Encoded<Vec<usize>>>(serde_json::json!({ "intervals": [1] }))
+                .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "intervals must be start and length pairs"
+        );
+    }
+
+    #[test]
+    fn adapters_reject_malformed_input() {
+        #[derive(Deserialize)]
+        struct Deltas {
+            #[serde(with = "crate::encoding::deltas")]
+            values: Vec<usize>,
+        }
+
+        let json = serde_json::json!({ "values": { "deltas": [1, 2] } });
+        assert_eq!(
+            serde_json::from_value::<Deltas>(json).unwrap().values,
+            [1, 3]
+        );
+
+        let json = serde_json::json!({ "values": { "intervals": [0, usize::MAX, 1] } });
+        assert!(serde_json::from_value::<Deltas>(json).is_err());
+    }
+}
@@ -2,10 +2,21 @@ use std::fmt::Display;
 
 use serde::Serialize;
 
+// Error codes Maelstrom defines for `error` replies.
+pub const TIMEOUT: usize = 0;
+pub const TEMPORARILY_UNAVAILABLE: usize = 11;
+pub const MALFORMED_REQUEST: usize = 12;
+pub const KEY_DOES_NOT_EXIST: usize = 20;
+pub const PRECONDITION_FAILED: usize = 22;
+
 #[derive(Debug, Serialize)]
 pub enum MaelstromError {
     IOError,
     MessageParseError,
+    ReplayDiverged,
+    Timeout,
+    RpcError(usize),
+    ConfigError(String),
 }
 
 impl Display for MaelstromError {
@@ -13,6 +24,12 @@ impl Display for MaelstromError {
         match self {
             Self::IOError => write!(f, "[maelstrom error] - io error"),
             Self::MessageParseError => write!(f, "[maelstrom error] - failed to parse message"),
+            Self::ReplayDiverged => write!(f, "[maelstrom error] - replay diverged from recording"),
+            Self::Timeout => write!(f, "[maelstrom error] - rpc timed out"),
+            Self::RpcError(code) => write!(f, "[maelstrom error] - rpc failed with code {code}"),
+            Self::ConfigError(problem) => {
+                write!(f, "[maelstrom error] - invalid config: {problem}")
+            }
         }
     }
 }
@@ -0,0 +1,289 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{Duration, Instant},
+};
+
+use crate::crdt::Crdt;
+
+struct Delta<C> {
+    delta: C,
+    // The peer the delta came from, which has no need to hear it back.
+    origin: Option<String>,
+    // The only peer to send the delta to, for a joining peer's copy of the
+    // state.
+    to: Option<String>,
+}
+
+#[derive(Default)]
+struct PeerState {
+    acked: u64,
+    sent: u64,
+    sent_at: Option<Instant>,
+}
+
+// Delta-state dissemination of a CRDT. Every update is kept as a delta under
+// the next local sequence number. A peer is sent the join of the deltas past
+// the highest sequence it has acknowledged, and deltas are dropped once every
+// peer has acknowledged them, so nothing is resent to a peer that has it and
+// memory is bounded by what is in flight. Deltas sent but not acknowledged
+// within the retransmit timeout are sent again.
+//
+// Deltas merged from a peer are passed on to the other peers only when
+// relaying, which a node needs unless it gossips with every other node.
+pub struct DeltaGossip<C: Crdt + PartialEq> {
+    state: C,
+    deltas: BTreeMap<u64, Delta<C>>,
+    next_seq: u64,
+    peers: HashMap<String, PeerState>,
+    relay: bool,
+    retransmit: Duration,
+}
+
+impl<C: Crdt + PartialEq> DeltaGossip<C> {
+    pub fn new(peers: &[String]) -> Self {
+        Self {
+            state: C::default(),
+            deltas: BTreeMap::new(),
+            next_seq: 1,
+            peers: peers
+                .iter()
+                .map(|peer| (peer.clone(), PeerState::default()))
+                .collect(),
+            relay: true,
+            retransmit: Duration::from_secs(1),
+        }
+    }
+
+    pub fn with_relay(mut self, relay: bool) -> Self {
+        self.relay = relay;
+        self
+    }
+
+    pub fn with_retransmit(mut self, retransmit: Duration) -> Self {
+        self.retransmit = retransmit;
+        self
+    }
+
+    pub fn state(&self) -> &C {
+        &self.state
+    }
+
+    pub fn peers(&self) -> impl Iterator<Item = &String> {
+        self.peers.keys()
+    }
+
+    // Replaces the peers, keeping the progress of those that stay. Deltas
+    // every old peer acknowledged may be gone already, so a new peer is sent
+    // the whole state as one delta of its own, followed by later deltas.
+    pub fn set_peers(&mut self, peers: &[String]) {
+        let mut known = std::mem::take(&mut self.peers);
+        for peer in peers {
+            let state = match known.remove(peer) {
+                Some(state) => state,
+                None => {
+                    let acked = self.next_seq - 1;
+                    self.record(self.state.clone(), None, Some(peer.clone()));
+                    PeerState {
+                        acked,
+                        sent: acked,
+                        sent_at: None,
+                    }
+                }
+            };
+
+            self.peers.insert(peer.clone(), state);
+        }
+    }
+
+    // Applies a local update; `mutate` returns the delta it produced.
+    pub fn update(&mut self, mutate: impl FnOnce(&mut C) -> C) {
+        let delta = mutate(&mut self.state);
+        self.record(delta, None, None);
+    }
+
+    // Merges a delta gossiped by `peer`.
+    pub fn receive(&mut self, peer: &str, delta: &C) {
+        let novel = delta.delta(&self.state);
+        self.state.merge(delta);
+        if self.relay {
+            self.record(novel, Some(peer.to_string()), None);
+        }
+    }
+
+    // The join of the deltas to send `peer` now, with the sequence number it
+    // acknowledges them by. Acknowledgements are cumulative, so every send
+    // carries all deltas past the last acknowledged one, not just those new
+    // since the previous send, which may have been lost. Deltas that came
+    // from `peer` itself or are meant for another peer are skipped, and count
+    // as acknowledged once nothing else is in flight.
+    pub fn pending(&mut self, peer: &str) -> Option<(u64, C)> {
+        let (&seq, _) = self.deltas.last_key_value()?;
+        let state = self.peers.get_mut(peer)?;
+        let timed_out = state
+            .sent_at
+            .is_some_and(|sent_at| sent_at.elapsed() >= self.retransmit);
+        if timed_out || state.sent < state.acked {
+            state.sent = state.acked;
+        }
+
+        if seq <= state.sent {
+            return None;
+        }
+
+        let mut pending = None;
+        for (_, delta) in self.deltas.range(state.acked + 1..) {
+            let skipped = delta.origin.as_deref() == Some(peer)
+                || delta.to.as_deref().is_some_and(|to| to != peer);
+            if !skipped {
+                pending.get_or_insert_with(C::default).merge(&delta.delta);
+            }
+        }
+
+        let idle = state.sent == state.acked;
+        state.sent = seq;
+        match pending {
+            Some(pending) => {
+                state.sent_at = Some(Instant::now());
+                Some((seq, pending))
+            }
+            None => {
+                if idle {
+                    self.ack(peer, seq);
+                }
+
+                None
+            }
+        }
+    }
+
+    pub fn ack(&mut self, peer: &str, seq: u64) {
+        if let Some(state) = self.peers.get_mut(peer) {
+            state.acked = state.acked.max(seq);
+            if state.acked >= state.sent {
+                state.sent = state.acked;
+                state.sent_at = None;
+            }
+        }
+
+        let low = self
+            .peers
+            .values()
+            .map(|state| state.acked)
+            .min()
+            .unwrap_or(self.next_seq);
+        self.deltas = self.deltas.split_off(&(low + 1));
+    }
+
+    fn record(&mut self, delta: C, origin: Option<String>, to: Option<String>) {
+        if delta == C::default() {
+            return;
+        }
+
+        self.deltas
+            .insert(self.next_seq, Delta { delta, origin, to });
+        self.next_seq += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crdt::GSet;
+
+    fn peers(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn set(elements: &[usize]) -> GSet<usize> {
+        let mut set = GSet::default();
+        for &element in elements {
+            set.insert(element);
+        }
+
+        set
+    }
+
+    fn insert(gossip: &mut DeltaGossip<GSet<usize>>, element: usize) {
+        gossip.update(|state| state.insert(element));
+    }
+
+    #[test]
+    fn sends_the_join_of_unacked_deltas_once() {
+        let mut gossip = DeltaGossip::new(&peers(&["n1"]));
+        insert(&mut gossip, 1);
+        insert(&mut gossip, 2);
+
+        assert_eq!(gossip.pending("n1"), Some((2, set(&[1, 2]))));
+        assert_eq!(gossip.pending("n1"), None);
+
+        // Unacknowledged deltas ride along with the next new one.
+        insert(&mut gossip, 3);
+        assert_eq!(gossip.pending("n1"), Some((3, set(&[1, 2, 3]))));
+
+        gossip.ack("n1", 3);
+        insert(&mut gossip, 4);
+        assert_eq!(gossip.pending("n1"), Some((4, set(&[4]))));
+    }
+
+    #[test]
+    fn prunes_deltas_every_peer_acknowledged() {
+        let mut gossip = DeltaGossip::new(&peers(&["n1", "n2"]));
+        insert(&mut gossip, 1);
+        insert(&mut gossip, 2);
+
+        gossip.ack("n1", 2);
+        assert_eq!(gossip.deltas.len(), 2);
+
+        gossip.ack("n2", 1);
+        assert_eq!(gossip.deltas.keys().collect::<Vec<_>>(), vec![&2]);
+
+        // Stale acknowledgements never move a peer back.
+        gossip.ack("n2", 0);
+        gossip.ack("n2", 2);
+        assert!(gossip.deltas.is_empty());
+        assert_eq!(gossip.pending("n2"), None);
+    }
+
+    #[test]
+    fn resends_after_the_retransmit_timeout() {
+        let mut gossip = DeltaGossip::new(&peers(&["n1"])).with_retransmit(Duration::ZERO);
+        insert(&mut gossip, 1);
+
+        assert_eq!(gossip.pending("n1"), Some((1, set(&[1]))));
+        assert_eq!(gossip.pending("n1"), Some((1, set(&[1]))));
+
+        gossip.ack("n1", 1);
+        assert_eq!(gossip.pending("n1"), None);
+    }
+
+    #[test]
+    fn relays_to_everyone_but_the_origin() {
+        let mut gossip = DeltaGossip::new(&peers(&["n1", "n2"]));
+        gossip.receive("n1", &set(&[1]));
+
+        assert_eq!(gossip.pending("n2"), Some((1, set(&[1]))));
+        // Skipped deltas count as acknowledged, so n1's copy can be pruned.
+        assert_eq!(gossip.pending("n1"), None);
+        gossip.ack("n2", 1);
+        assert!(gossip.deltas.is_empty());
+
+        let mut quiet = DeltaGossip::new(&peers(&["n1", "n2"])).with_relay(false);
+        quiet.receive("n1", &set(&[1]));
+        assert_eq!(quiet.pending("n2"), None);
+        assert_eq!(quiet.state(), &set(&[1]));
+    }
+
+    #[test]
+    fn new_peers_are_sent_the_whole_state() {
+        let mut gossip = DeltaGossip::new(&peers(&["n1"]));
+        insert(&mut gossip, 1);
+        gossip.ack("n1", 1);
+        assert!(gossip.deltas.is_empty());
+
+        gossip.set_peers(&peers(&["n1", "n2"]));
+        insert(&mut gossip, 2);
+
+        assert_eq!(gossip.pending("n1"), Some((3, set(&[2]))));
+        assert_eq!(gossip.pending("n2"), Some((3, set(&[1, 2]))));
+    }
+}
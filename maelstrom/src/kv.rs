@@ -0,0 +1,180 @@
+use std::time::Duration;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    error::{MaelstromError, KEY_DOES_NOT_EXIST, PRECONDITION_FAILED},
+    service::Service,
+};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KvKind {
+    Seq,
+    Lin,
+    Lww,
+}
+
+impl KvKind {
+    pub const ALL: [KvKind; 3] = [KvKind::Seq, KvKind::Lin, KvKind::Lww];
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::Seq => "seq-kv",
+            Self::Lin => "lin-kv",
+            Self::Lww => "lww-kv",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum KvRequest {
+    Read {
+        key: Value,
+    },
+    Write {
+        key: Value,
+        value: Value,
+    },
+    Cas {
+        key: Value,
+        from: Value,
+        to: Value,
+        #[serde(default)]
+        create_if_not_exists: bool,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum KvResponse {
+    ReadOk { value: Value },
+    WriteOk,
+    CasOk,
+    Error { code: usize, text: String },
+}
+
+impl KvResponse {
+    pub fn error(code: usize, text: impl Into<String>) -> Self {
+        Self::Error {
+            code,
+            text: text.into(),
+        }
+    }
+}
+
+// A blocking client for one of Maelstrom's key-value services. Every call is
+// a round trip through `Service::rpc`.
+#[derive(Debug, Clone)]
+pub struct KvClient {
+    kind: KvKind,
+    timeout: Duration,
+}
+
+impl KvClient {
+    pub fn new(kind: KvKind) -> Self {
+        Self {
+            kind,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    pub fn seq() -> Self {
+        Self::new(KvKind::Seq)
+    }
+
+    pub fn lin() -> Self {
+        Self::new(KvKind::Lin)
+    }
+
+    pub fn lww() -> Self {
+        Self::new(KvKind::Lww)
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn kind(&self) -> KvKind {
+        self.kind
+    }
+
+    fn call(
+        &self,
+        service: &mut Service,
+        request: KvRequest,
+    ) -> Result<KvResponse, MaelstromError> {
+        service.rpc(self.kind.id(), request, self.timeout)
+    }
+
+    // Returns `None` when the key has never been written.
+    pub fn read<T: DeserializeOwned>(
+        &self,
+        service: &mut Service,
+        key: &str,
+    ) -> Result<Option<T>, MaelstromError> {
+        let request = KvRequest::Read { key: key.into() };
+        match self.call(service, request)? {
+            KvResponse::ReadOk { value } => serde_json::from_value(value)
+                .map(Some)
+                .map_err(|_| MaelstromError::MessageParseError),
+            KvResponse::Error {
+                code: KEY_DOES_NOT_EXIST,
+                ..
+            } => Ok(None),
+            KvResponse::Error { code, .. } => Err(MaelstromError::RpcError(code)),
+            _ => Err(MaelstromError::MessageParseError),
+        }
+    }
+
+    pub fn write<T: Serialize>(
+        &self,
+        service: &mut Service,
+        key: &str,
+        value: &T,
+    ) -> Result<(), MaelstromError> {
+        let request = KvRequest::Write {
+            key: key.into(),
+            value: serde_json::to_value(value).map_err(|_| MaelstromError::IOError)?,
+        };
+
+        match self.call(service, request)? {
+            KvResponse::WriteOk => Ok(()),
+            KvResponse::Error { code, .. } => Err(MaelstromError::RpcError(code)),
+            _ => Err(MaelstromError::MessageParseError),
+        }
+    }
+
+    // Returns whether the swap happened; a missing key or a current value
+    // other than `from` both count as a failed precondition.
+    pub fn cas<T: Serialize>(
+        &self,
+        service: &mut Service,
+        key: &str,
+        from: &T,
+        to: &T,
+        create_if_not_exists: bool,
+    ) -> Result<bool, MaelstromError> {
+        let to_value = |value: &T| serde_json::to_value(value).map_err(|_| MaelstromError::IOError);
+        let request = KvRequest::Cas {
+            key: key.into(),
+            from: to_value(from)?,
+            to: to_value(to)?,
+            create_if_not_exists,
+        };
+
+        match self.call(service, request)? {
+            KvResponse::CasOk => Ok(true),
+            KvResponse::Error {
+                code: KEY_DOES_NOT_EXIST | PRECONDITION_FAILED,
+                ..
+            } => Ok(false),
+            KvResponse::Error { code, .. } => Err(MaelstromError::RpcError(code)),
+            _ => Err(MaelstromError::MessageParseError),
+        }
+    }
+}
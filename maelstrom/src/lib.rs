@@ -1,4 +1,17 @@
+pub mod anti_entropy;
+pub mod batch;
+pub mod config;
+pub mod crdt;
+pub mod encoding;
 pub mod error;
+pub mod gossip;
+pub mod kv;
+mod link;
+pub mod merkle;
 pub mod message;
 pub mod node;
-pub mod service;
\ No newline at end of file
+pub mod plumtree;
+pub mod service;
+pub mod topology;
+pub mod trace;
+pub mod txn;
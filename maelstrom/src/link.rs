@@ -0,0 +1,335 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// The most payloads a retransmitted frame carries; later ones wait for the
+// earlier ones to be acknowledged.
+const MAX_FRAME: usize = 64;
+
+// The most retransmit intervals a peer that acknowledges nothing is skipped
+// for between retransmits.
+const MAX_BACKOFF: u32 = 8;
+
+// The wire format of reliable links. Data frames carry a run of peer
+// payloads under consecutive per-destination sequence numbers, starting at
+// `seq`, and acks the highest sequence number below which everything has
+// been delivered. Sequence numbers start at 1.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum Frame {
+    LinkData { seq: u64, data: Vec<Value> },
+    LinkAck { ack: u64 },
+}
+
+struct Unacked {
+    data: Value,
+    // Whether the frame was already unacknowledged at the last retransmit,
+    // so has had at least a full interval to be acknowledged.
+    due: bool,
+}
+
+#[derive(Default)]
+struct Outbound {
+    last_seq: u64,
+    unacked: BTreeMap<u64, Unacked>,
+    // Retransmit intervals to skip before the next retransmit, doubling while
+    // the peer acknowledges nothing, and how many have been skipped so far.
+    backoff: u32,
+    skipped: u32,
+}
+
+#[derive(Default)]
+struct Inbound {
+    delivered: u64,
+    // Frames that arrived ahead of a gap, held back to keep delivery in
+    // order.
+    pending: BTreeMap<u64, Value>,
+}
+
+// Reliable point-to-point links to every peer. Frames are retransmitted until
+// acknowledged, and received frames are delivered exactly once and in the
+// order they were sent, so a lost, duplicated or reordered message looks to
+// the node like a delayed one.
+#[derive(Default)]
+pub(crate) struct Links {
+    outbound: HashMap<String, Outbound>,
+    inbound: HashMap<String, Inbound>,
+}
+
+impl Links {
+    pub fn send(&mut self, peer: &str, data: Value) -> Frame {
+        let outbound = self.outbound.entry(peer.to_string()).or_default();
+        outbound.last_seq += 1;
+        let seq = outbound.last_seq;
+        outbound.unacked.insert(
+            seq,
+            Unacked {
+                data: data.clone(),
+                due: false,
+            },
+        );
+
+        Frame::LinkData {
+            seq,
+            data: vec![data],
+        }
+    }
+
+    // Settles everything up to `ack`. A peer that acknowledges anything new
+    // is reachable again, so its backoff starts over.
+    pub fn ack(&mut self, peer: &str, ack: u64) {
+        if let Some(outbound) = self.outbound.get_mut(peer) {
+            let unacked = outbound.unacked.len();
+            outbound.unacked = outbound.unacked.split_off(&ack.saturating_add(1));
+            if outbound.unacked.len() < unacked {
+                outbound.backoff = 0;
+                outbound.skipped = 0;
+            }
+        }
+    }
+
+    // Returns the payloads a data frame makes deliverable, in order, and the
+    // one ack to send back for all of them. Duplicates deliver nothing but are
+    // acknowledged again, in case the first ack was lost.
+    pub fn receive(&mut self, peer: &str, seq: u64, data: Vec<Value>) -> (Vec<Value>, Frame) {
+        let inbound = self.inbound.entry(peer.to_string()).or_default();
+        for (seq, data) in (seq..).zip(data) {
+            if seq > inbound.delivered {
+                inbound.pending.insert(seq, data);
+            }
+        }
+
+        let mut delivered = vec![];
+        while let Some(data) = inbound.pending.remove(&(inbound.delivered + 1)) {
+            inbound.delivered += 1;
+            delivered.push(data);
+        }
+
+        let ack = inbound.delivered;
+        (delivered, Frame::LinkAck { ack })
+    }
+
+    // The frames to send again: for each peer, one frame with up to
+    // `MAX_FRAME` of the payloads that have gone unacknowledged since the
+    // previous call. Acks are cumulative, so those always have consecutive
+    // sequence numbers. A peer that stays silent is retransmitted to less and
+    // less often, so an unreachable one is not flooded.
+    pub fn retransmit(&mut self) -> Vec<(String, Frame)> {
+        let mut frames = vec![];
+        for (peer, outbound) in &mut self.outbound {
+            let mut due = None;
+            for (&seq, unacked) in &mut outbound.unacked {
+                if unacked.due {
+                    let (_, data) = due.get_or_insert((seq, vec![]));
+                    if data.len() < MAX_FRAME {
+                        data.push(unacked.data.clone());
+                    }
+                }
+
+                unacked.due = true;
+            }
+
+            let Some((seq, data)) = due else {
+                continue;
+            };
+
+            if outbound.skipped < outbound.backoff {
+                outbound.skipped += 1;
+                continue;
+            }
+
+            outbound.skipped = 0;
+            outbound.backoff = (outbound.backoff * 2 + 1).min(MAX_BACKOFF);
+            frames.push((peer.clone(), Frame::LinkData { seq, data }));
+        }
+
+        frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn data(frame: Frame) -> (u64, Vec<Value>) {
+        match frame {
+            Frame::LinkData { seq, data } => (seq, data),
+            Frame::LinkAck { .. } => panic!("expected a data frame"),
+        }
+    }
+
+    #[test]
+    fn delivers_in_order_across_gaps() {
+        let mut sender = Links::default();
+        let frames = (1..=3)
+            .map(|value| data(sender.send("n1", json!(value))))
+            .collect::<Vec<_>>();
+
+        let mut receiver = Links::default();
+        let (seq, payload) = frames[2].clone();
+        assert_eq!(
+            receiver.receive("n0", seq, payload),
+            (vec![], Frame::LinkAck { ack: 0 })
+        );
+
+        let (seq, payload) = frames[0].clone();
+        assert_eq!(
+            receiver.receive("n0", seq, payload),
+            (vec![json!(1)], Frame::LinkAck { ack: 1 })
+        );
+
+        let (seq, payload) = frames[1].clone();
+        assert_eq!(
+            receiver.receive("n0", seq, payload),
+            (vec![json!(2), json!(3)], Frame::LinkAck { ack: 3 })
+        );
+    }
+
+    #[test]
+    fn suppresses_duplicates_but_acks_them_again() {
+        let mut receiver = Links::default();
+        let (delivered, _) = receiver.receive("n0", 1, vec![json!("a"), json!("b")]);
+        assert_eq!(delivered, vec![json!("a"), json!("b")]);
+
+        assert_eq!(
+            receiver.receive("n0", 1, vec![json!("a")]),
+            (vec![], Frame::LinkAck { ack: 2 })
+        );
+
+        // A retransmitted run that overlaps what was delivered only delivers
+        // the rest.
+        assert_eq!(
+            receiver.receive("n0", 2, vec![json!("b"), json!("c")]),
+            (vec![json!("c")], Frame::LinkAck { ack: 3 })
+        );
+    }
+
+    #[test]
+    fn sequences_are_per_peer() {
+        let mut receiver = Links::default();
+        let (delivered, _) = receiver.receive("n0", 1, vec![json!(1)]);
+        assert_eq!(delivered, vec![json!(1)]);
+        let (delivered, ack) = receiver.receive("n1", 1, vec![json!(1)]);
+        assert_eq!(delivered, vec![json!(1)]);
+        assert_eq!(ack, Frame::LinkAck { ack: 1 });
+    }
+
+    #[test]
+    fn cumulative_acks_settle_everything_before_them() {
+        let mut sender = Links::default();
+        for value in 1..=4 {
+            sender.send("n1", json!(value));
+        }
+
+        sender.retransmit();
+        sender.ack("n1", 3);
+        assert_eq!(
+            sender.retransmit(),
+            vec![(
+                "n1".to_string(),
+                Frame::LinkData {
+                    seq: 4,
+                    data: vec![json!(4)]
+                }
+            )]
+        );
+
+        sender.ack("n1", 4);
+        sender.retransmit();
+        assert!(sender.retransmit().is_empty());
+    }
+
+    #[test]
+    fn retransmits_a_peers_unacked_frames_as_one() {
+        let mut sender = Links::default();
+        sender.send("n1", json!("a"));
+        sender.send("n1", json!("b"));
+        sender.send("n2", json!("c"));
+
+        // Nothing has had a full interval to be acknowledged yet.
+        assert!(sender.retransmit().is_empty());
+
+        sender.send("n1", json!("d"));
+        let mut frames = sender.retransmit();
+        frames.sort_by(|(a, _), (b, _)| a.cmp(b));
+        assert_eq!(
+            frames,
+            vec![
+                (
+                    "n1".to_string(),
+                    Frame::LinkData {
+                        seq: 1,
+                        data: vec![json!("a"), json!("b")]
+                    }
+                ),
+                (
+                    "n2".to_string(),
+                    Frame::LinkData {
+                        seq: 1,
+                        data: vec![json!("c")]
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn acks_past_the_last_sequence_number_are_harmless() {
+        let mut sender = Links::default();
+        sender.send("n1", json!(1));
+        sender.ack("n1", u64::MAX);
+        sender.retransmit();
+        assert!(sender.retransmit().is_empty());
+    }
+
+    #[test]
+    fn caps_retransmitted_frames() {
+        let mut sender = Links::default();
+        for value in 0..MAX_FRAME + 10 {
+            sender.send("n1", json!(value));
+        }
+
+        sender.retransmit();
+        let (seq, first) = data(sender.retransmit().remove(0).1);
+        assert_eq!(seq, 1);
+        assert_eq!(first.len(), MAX_FRAME);
+
+        // The rest go out once the first ones are acknowledged.
+        sender.ack("n1", MAX_FRAME as u64);
+        let (seq, rest) = data(sender.retransmit().remove(0).1);
+        assert_eq!(seq, MAX_FRAME as u64 + 1);
+        assert_eq!(rest.len(), 10);
+    }
+
+    #[test]
+    fn backs_off_from_silent_peers_until_they_ack() {
+        let mut sender = Links::default();
+        sender.send("n1", json!(1));
+        sender.retransmit();
+
+        // Retransmits go out with 1, 3 and 7 intervals skipped in between,
+        // and then never more than 8.
+        let sent = (0..40)
+            .map(|_| !sender.retransmit().is_empty())
+            .collect::<Vec<_>>();
+        let gaps = sent
+            .iter()
+            .enumerate()
+            .filter(|(_, sent)| **sent)
+            .map(|(at, _)| at)
+            .collect::<Vec<_>>()
+            .windows(2)
+            .map(|pair| pair[1] - pair[0] - 1)
+            .collect::<Vec<_>>();
+        assert_eq!(gaps, vec![1, 3, 7, 8, 8]);
+
+        // An ack of anything new resets the backoff.
+        sender.send("n1", json!(2));
+        sender.ack("n1", 1);
+        sender.retransmit();
+        assert!(!sender.retransmit().is_empty());
+    }
+}
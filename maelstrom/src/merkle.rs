@@ -0,0 +1,271 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+use serde::{Deserialize, Serialize};
+
+// A node of the tree by level and index within the level; the root is level
+// 0 and the leaf buckets are the deepest level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Node(pub u8, pub u32);
+
+impl Node {
+    pub const ROOT: Node = Node(0, 0);
+}
+
+// Where two replicas' trees go from here, given one side's hashes for some
+// nodes of a level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    // Every node matched.
+    InSync,
+    // Some inner nodes differ; these are this side's hashes of their
+    // children, for the other side to compare in turn.
+    Descend(Vec<(Node, u64)>),
+    // Some leaf buckets differ, and the entries in them are to be exchanged.
+    Exchange(Vec<Node>),
+}
+
+// A Merkle tree over a replicated map, for finding where two replicas differ
+// without sending either one. Each key goes in the leaf bucket picked by its
+// hash, and each inner node's hash is the XOR of the entry hashes below it, so
+// updating an entry only touches the hashes on its path.
+//
+// Replicas reconcile by comparing hashes level by level, starting with the
+// root, and recursing only into the subtrees that differ, then exchanging the
+// entries of the differing leaf buckets. A set is a map whose entries all
+// hash the same; a key-value replica hashes each key's value or version, so
+// replicas that disagree on a value differ too.
+pub struct MerkleTree<K> {
+    fanout: u32,
+    depth: u8,
+    // The hashes of each level's nodes, root first.
+    levels: Vec<Vec<u64>>,
+    buckets: Vec<HashMap<K, u64>>,
+}
+
+impl<K: Clone + Eq + Hash> Default for MerkleTree<K> {
+    fn default() -> Self {
+        Self::new(16, 2)
+    }
+}
+
+impl<K: Clone + Eq + Hash> MerkleTree<K> {
+    // A tree with `fanout` children per inner node and `fanout^depth` leaf
+    // buckets. Replicas that reconcile must agree on both.
+    pub fn new(fanout: u32, depth: u8) -> Self {
+        let fanout = fanout.max(2);
+        let levels = (0..=depth)
+            .map(|level| vec![0; fanout.pow(level as u32) as usize])
+            .collect::<Vec<_>>();
+
+        let buckets = (0..levels[depth as usize].len())
+            .map(|_| HashMap::new())
+            .collect();
+
+        Self {
+            fanout,
+            depth,
+            levels,
+            buckets,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buckets.iter().map(HashMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.buckets[self.bucket_of(key)].contains_key(key)
+    }
+
+    pub fn hash(&self, node: Node) -> u64 {
+        self.levels
+            .get(node.0 as usize)
+            .and_then(|level| level.get(node.1 as usize))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    // Sets the entry for `key`, which is hashed along with the key; returns
+    // whether the tree changed.
+    pub fn insert(&mut self, key: K, entry: &impl Hash) -> bool {
+        let hash = hash_of(&(&key, entry));
+        let bucket = self.bucket_of(&key);
+        match self.buckets[bucket].insert(key, hash) {
+            Some(old) if old == hash => false,
+            old => {
+                self.update(bucket, hash ^ old.unwrap_or(0));
+                true
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &K) -> bool {
+        let bucket = self.bucket_of(key);
+        match self.buckets[bucket].remove(key) {
+            Some(old) => {
+                self.update(bucket, old);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Compares `hashes`, the other side's hashes for nodes of one level, with
+    // this tree's.
+    pub fn compare(&self, hashes: &[(Node, u64)]) -> Step {
+        let differing = hashes
+            .iter()
+            .filter(|&&(node, hash)| node.0 <= self.depth && self.hash(node) != hash)
+            .map(|&(node, _)| node)
+            .collect::<Vec<_>>();
+
+        match differing.first() {
+            None => Step::InSync,
+            Some(node) if node.0 == self.depth => Step::Exchange(differing),
+            Some(_) => Step::Descend(
+                differing
+                    .iter()
+                    .flat_map(|&Node(level, index)| {
+                        (index * self.fanout..(index + 1) * self.fanout)
+                            .map(move |child| Node(level + 1, child))
+                    })
+                    .map(|child| (child, self.hash(child)))
+                    .collect(),
+            ),
+        }
+    }
+
+    // The keys in the given leaf buckets.
+    pub fn keys<'a>(&'a self, buckets: &'a [Node]) -> impl Iterator<Item = &'a K> {
+        buckets
+            .iter()
+            .filter(|node| node.0 == self.depth)
+            .filter_map(|node| self.buckets.get(node.1 as usize))
+            .flat_map(HashMap::keys)
+    }
+
+    // The root's hash, to start reconciling with.
+    pub fn root(&self) -> Vec<(Node, u64)> {
+        vec![(Node::ROOT, self.hash(Node::ROOT))]
+    }
+
+    fn bucket_of(&self, key: &K) -> usize {
+        (hash_of(key) % self.buckets.len() as u64) as usize
+    }
+
+    fn update(&mut self, bucket: usize, change: u64) {
+        let mut index = bucket;
+        for level in self.levels.iter_mut().rev() {
+            level[index] ^= change;
+            index /= self.fanout as usize;
+        }
+    }
+}
+
+fn hash_of(value: &impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn tree(values: impl IntoIterator<Item = usize>) -> MerkleTree<usize> {
+        let mut tree = MerkleTree::new(4, 3);
+        for value in values {
+            tree.insert(value, &());
+        }
+
+        tree
+    }
+
+    fn keys(tree: &MerkleTree<usize>) -> HashSet<usize> {
+        let buckets = (0..64).map(|index| Node(3, index)).collect::<Vec<_>>();
+        tree.keys(&buckets).copied().collect()
+    }
+
+    // Reconciles the way two nodes do: `a` sends its root hash, the sides
+    // take turns answering each other's hashes with those of the children
+    // that differ, and the entries of the differing buckets are exchanged.
+    // Returns how many hash messages were sent and how many keys.
+    fn reconcile(a: &mut MerkleTree<usize>, b: &mut MerkleTree<usize>) -> (usize, usize) {
+        let mut hashes = a.root();
+        let mut messages = 1;
+        let buckets = loop {
+            let answering = if messages % 2 == 1 { &*b } else { &*a };
+            match answering.compare(&hashes) {
+                Step::InSync => return (messages, 0),
+                Step::Descend(children) => {
+                    hashes = children;
+                    messages += 1;
+                }
+                Step::Exchange(buckets) => break buckets,
+            }
+        };
+
+        let in_a = a.keys(&buckets).copied().collect::<HashSet<_>>();
+        let in_b = b.keys(&buckets).copied().collect::<HashSet<_>>();
+        for &key in in_b.difference(&in_a) {
+            a.insert(key, &());
+        }
+
+        for &key in in_a.difference(&in_b) {
+            b.insert(key, &());
+        }
+
+        (messages, in_a.len() + in_b.len())
+    }
+
+    #[test]
+    fn diverged_sets_converge_exchanging_only_differing_buckets() {
+        let mut a = tree((0..1000).chain([5000]));
+        let mut b = tree((0..1000).filter(|&value| value != 17).chain([7000]));
+
+        let (messages, sent) = reconcile(&mut a, &mut b);
+        assert_eq!(keys(&a), keys(&b));
+        assert!(keys(&a).contains(&17) && keys(&a).contains(&7000));
+        assert_eq!(messages, 4, "one hash message per level");
+        assert!(sent < 200, "sent {sent} keys");
+
+        assert_eq!(reconcile(&mut a, &mut b), (1, 0));
+    }
+
+    #[test]
+    fn hashes_only_depend_on_the_entries() {
+        let mut a = tree(0..100);
+        let b = tree((0..100).rev());
+        assert_eq!(a.root(), b.root());
+
+        assert!(a.insert(100, &()));
+        assert!(!a.insert(100, &()));
+        assert_ne!(a.root(), b.root());
+
+        assert!(a.remove(&100));
+        assert!(!a.remove(&100));
+        assert_eq!(a.root(), b.root());
+        assert_eq!(a.len(), 100);
+    }
+
+    #[test]
+    fn entries_that_differ_in_value_differ_in_hash() {
+        let mut a = MerkleTree::<&str>::default();
+        let mut b = MerkleTree::<&str>::default();
+        a.insert("key", &1);
+        b.insert("key", &2);
+
+        assert!(matches!(b.compare(&a.root()), Step::Descend(_)));
+        b.insert("key", &1);
+        assert_eq!(b.compare(&a.root()), Step::InSync);
+    }
+}
@@ -11,10 +11,40 @@ pub struct Message<P> {
 }
 
 impl<P> Message<P> {
+    pub fn new(
+        src: String,
+        dest: String,
+        message_id: Option<usize>,
+        in_reply_to: Option<usize>,
+        payload: P,
+    ) -> Self {
+        Self {
+            src,
+            dest,
+            body: MessageBody {
+                message_id,
+                in_reply_to,
+                payload,
+            },
+        }
+    }
+
     pub fn src(&self) -> &str {
         &self.src
     }
 
+    pub fn dest(&self) -> &str {
+        &self.dest
+    }
+
+    pub fn message_id(&self) -> Option<usize> {
+        self.body.message_id
+    }
+
+    pub fn in_reply_to(&self) -> Option<usize> {
+        self.body.in_reply_to
+    }
+
     pub fn payload(&self) -> &P {
         &self.body.payload
     }
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    config::NodeConfig,
     error::MaelstromError,
     message::{InitializationRequest, Message},
     service::Service,
@@ -10,8 +11,9 @@ pub trait MaelstromNode {
     type InputPayload: for<'a> Deserialize<'a>;
     type OutputPayload: Serialize;
     type PeerPayload: Serialize + for<'a> Deserialize<'a>;
+    type Config: NodeConfig;
 
-    fn new(init_message: &Message<InitializationRequest>) -> Self;
+    fn new(init_message: &Message<InitializationRequest>, config: Self::Config) -> Self;
     fn handle(
         &mut self,
         message: &Message<Self::InputPayload>,
@@ -20,6 +22,14 @@ pub trait MaelstromNode {
     where
         Self: Sized;
 
+    fn start(&mut self, _: &mut Service) -> Result<(), MaelstromError> {
+        Ok(())
+    }
+
+    fn handle_timer(&mut self, _: usize, _: &mut Service) -> Result<(), MaelstromError> {
+        Ok(())
+    }
+
     fn handle_peer(
         &mut self,
         _: &Message<Self::PeerPayload>,
@@ -0,0 +1,474 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    time::{Duration, Instant},
+};
+
+// What a node sends a peer. Each maps onto one of the node's own peer
+// payloads; a graft is answered with the values `graft` returns, handed to
+// `graft_ok` on arrival, and an announcement with an acknowledgement of the
+// values it listed. Values travel with the root, the node they were broadcast
+// at, whose tree they are pushed along.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outgoing<T> {
+    Gossip { root: String, values: Vec<T> },
+    IHave(Vec<(String, T)>),
+    Graft { root: String, values: Vec<T> },
+    Prune { root: String },
+}
+
+// A node's links in one root's tree.
+struct Links {
+    eager: HashSet<String>,
+    lazy: HashSet<String>,
+}
+
+struct Missing {
+    root: String,
+    // Peers that announced the value, in the order they did.
+    announcers: Vec<String>,
+    since: Instant,
+}
+
+// Plumtree epidemic broadcast trees, with a tree per root. Values are pushed
+// eagerly to the root's eager peers, which start out as all of them. A peer
+// that pushes a value the node already has is pruned to a lazy peer of that
+// root, so the eager links settle into a spanning tree per root. Sharing one
+// tree between roots makes links flap, since an edge that is redundant for
+// one root is often needed by another.
+//
+// Every value is also announced, in batches every lazy interval, to each of
+// the other peers not known to have it, eager ones included, until the peer
+// acknowledges the announcement. A value announced but not received within
+// the graft timeout is grafted from one of its announcers, which makes that
+// link eager again, so the trees heal around lost pushes and partitions.
+pub struct Plumtree<T> {
+    id: String,
+    peers: Vec<String>,
+    trees: HashMap<String, Links>,
+    received: HashSet<T>,
+    roots: HashMap<T, String>,
+    unannounced: HashMap<String, HashSet<T>>,
+    missing: HashMap<T, Missing>,
+    lazy_interval: Duration,
+    announced_at: Instant,
+    graft_timeout: Duration,
+}
+
+impl<T: Clone + Eq + Hash> Plumtree<T> {
+    pub fn new(id: &str, peers: &[String]) -> Self {
+        Self {
+            id: id.to_string(),
+            peers: peers.to_vec(),
+            trees: HashMap::new(),
+            received: HashSet::new(),
+            roots: HashMap::new(),
+            unannounced: peers
+                .iter()
+                .map(|peer| (peer.clone(), HashSet::new()))
+                .collect(),
+            missing: HashMap::new(),
+            lazy_interval: Duration::from_secs(1),
+            announced_at: Instant::now(),
+            graft_timeout: Duration::from_millis(500),
+        }
+    }
+
+    pub fn with_lazy_interval(mut self, lazy_interval: Duration) -> Self {
+        self.lazy_interval = lazy_interval;
+        self
+    }
+
+    pub fn with_graft_timeout(mut self, graft_timeout: Duration) -> Self {
+        self.graft_timeout = graft_timeout;
+        self
+    }
+
+    pub fn received(&self) -> &HashSet<T> {
+        &self.received
+    }
+
+    // Replaces the peers, keeping the links of those that stay; new peers
+    // start eager in every tree and are announced every value.
+    pub fn set_peers(&mut self, peers: &[String]) {
+        for links in self.trees.values_mut() {
+            links.lazy.retain(|peer| peers.contains(peer));
+            links.eager = peers
+                .iter()
+                .filter(|&peer| !links.lazy.contains(peer))
+                .cloned()
+                .collect();
+        }
+
+        self.unannounced.retain(|peer, _| peers.contains(peer));
+        for peer in peers {
+            if !self.unannounced.contains_key(peer) {
+                let values = self.received.clone();
+                self.unannounced.insert(peer.clone(), values);
+            }
+        }
+
+        self.peers = peers.to_vec();
+    }
+
+    // Delivers a value broadcast at this node.
+    pub fn broadcast(&mut self, value: T) -> Vec<(String, Outgoing<T>)> {
+        let root = self.id.clone();
+        self.deliver(None, &root, vec![value])
+    }
+
+    // Handles values of `root` pushed eagerly by `peer`. A push of nothing
+    // new means the link is redundant in that tree, so it is pruned.
+    pub fn gossip(&mut self, peer: &str, root: &str, values: &[T]) -> Vec<(String, Outgoing<T>)> {
+        if values.is_empty() {
+            return vec![];
+        }
+
+        let novel = self.novel(peer, values);
+        if novel.is_empty() {
+            self.make_lazy(peer, root);
+            let root = root.to_string();
+            return vec![(peer.to_string(), Outgoing::Prune { root })];
+        }
+
+        self.make_eager(peer, root);
+        self.deliver(Some(peer), root, novel)
+    }
+
+    // Handles the values `peer` sent back for a graft of `root`. The values
+    // may have arrived another way in the meantime, which says nothing about
+    // the link the node just grafted, so it is never pruned here.
+    pub fn graft_ok(&mut self, peer: &str, root: &str, values: &[T]) -> Vec<(String, Outgoing<T>)> {
+        let novel = self.novel(peer, values);
+        if novel.is_empty() {
+            return vec![];
+        }
+
+        self.deliver(Some(peer), root, novel)
+    }
+
+    pub fn prune(&mut self, peer: &str, root: &str) {
+        self.make_lazy(peer, root);
+    }
+
+    // Notes the values `peer` announced that are still missing here. The
+    // caller acknowledges every value listed.
+    pub fn ihave(&mut self, peer: &str, announced: &[(String, T)]) {
+        for (root, value) in announced {
+            self.known_by(peer, std::slice::from_ref(value));
+            if self.received.contains(value) {
+                continue;
+            }
+
+            let missing = self
+                .missing
+                .entry(value.clone())
+                .or_insert_with(|| Missing {
+                    root: root.clone(),
+                    announcers: vec![],
+                    since: Instant::now(),
+                });
+
+            if !missing.announcers.iter().any(|announcer| announcer == peer) {
+                missing.announcers.push(peer.to_string());
+            }
+        }
+    }
+
+    pub fn ihave_ok(&mut self, peer: &str, values: &[T]) {
+        self.known_by(peer, values);
+    }
+
+    // Makes `peer` an eager link of `root` and returns the values to send
+    // back: those it asked for, which it lacked when it asked, that this node
+    // has.
+    pub fn graft(&mut self, peer: &str, root: &str, values: &[T]) -> Vec<T> {
+        self.make_eager(peer, root);
+        values
+            .iter()
+            .filter(|value| self.received.contains(*value))
+            .cloned()
+            .collect()
+    }
+
+    // Announces values every lazy interval and grafts those missing for
+    // longer than the graft timeout. Each missing value is grafted from its
+    // first announcer, which then moves to the back in case it fails too.
+    pub fn tick(&mut self) -> Vec<(String, Outgoing<T>)> {
+        let mut outgoing = vec![];
+        if self.announced_at.elapsed() >= self.lazy_interval {
+            self.announced_at = Instant::now();
+            for (peer, values) in &self.unannounced {
+                if !values.is_empty() {
+                    let announced = values
+                        .iter()
+                        .map(|value| (self.roots[value].clone(), value.clone()))
+                        .collect();
+
+                    outgoing.push((peer.clone(), Outgoing::IHave(announced)));
+                }
+            }
+        }
+
+        let mut grafts: HashMap<(String, String), Vec<T>> = HashMap::new();
+        for (value, missing) in &mut self.missing {
+            if missing.since.elapsed() < self.graft_timeout || missing.announcers.is_empty() {
+                continue;
+            }
+
+            let announcer = missing.announcers.remove(0);
+            grafts
+                .entry((announcer.clone(), missing.root.clone()))
+                .or_default()
+                .push(value.clone());
+
+            missing.announcers.push(announcer);
+            missing.since = Instant::now();
+        }
+
+        for ((peer, root), values) in grafts {
+            self.make_eager(&peer, &root);
+            outgoing.push((peer, Outgoing::Graft { root, values }));
+        }
+
+        outgoing
+    }
+
+    fn links(&mut self, root: &str) -> &mut Links {
+        let peers = &self.peers;
+        self.trees.entry(root.to_string()).or_insert_with(|| Links {
+            eager: peers.iter().cloned().collect(),
+            lazy: HashSet::new(),
+        })
+    }
+
+    fn deliver(
+        &mut self,
+        from: Option<&str>,
+        root: &str,
+        values: Vec<T>,
+    ) -> Vec<(String, Outgoing<T>)> {
+        for value in &values {
+            self.received.insert(value.clone());
+            self.roots.insert(value.clone(), root.to_string());
+            self.missing.remove(value);
+        }
+
+        let eager = self
+            .links(root)
+            .eager
+            .iter()
+            .filter(|&peer| Some(peer.as_str()) != from)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        // Eager peers are announced the values too, in case the push is lost.
+        for (peer, unannounced) in &mut self.unannounced {
+            if Some(peer.as_str()) != from {
+                unannounced.extend(values.iter().cloned());
+            }
+        }
+
+        eager
+            .into_iter()
+            .map(|peer| {
+                let root = root.to_string();
+                let values = values.clone();
+                (peer, Outgoing::Gossip { root, values })
+            })
+            .collect()
+    }
+
+    // The values `peer` sent that were not received yet, noting that `peer`
+    // has all of them.
+    fn novel(&mut self, peer: &str, values: &[T]) -> Vec<T> {
+        self.known_by(peer, values);
+        values
+            .iter()
+            .filter(|value| !self.received.contains(*value))
+            .cloned()
+            .collect()
+    }
+
+    fn known_by(&mut self, peer: &str, values: &[T]) {
+        if let Some(unannounced) = self.unannounced.get_mut(peer) {
+            for value in values {
+                unannounced.remove(value);
+            }
+        }
+    }
+
+    fn make_eager(&mut self, peer: &str, root: &str) {
+        let links = self.links(root);
+        if links.lazy.remove(peer) {
+            links.eager.insert(peer.to_string());
+        }
+    }
+
+    fn make_lazy(&mut self, peer: &str, root: &str) {
+        let links = self.links(root);
+        if links.eager.remove(peer) {
+            links.lazy.insert(peer.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::*;
+
+    // Nodes on a line, n0 - n1 - n2, so every push is needed. Announcements
+    // and grafts go out on every tick, and the network loses the first push
+    // over the link `lose`, if any.
+    struct Network {
+        trees: HashMap<String, Plumtree<usize>>,
+        sent: VecDeque<(String, String, Outgoing<usize>)>,
+        lose: Option<(String, String)>,
+    }
+
+    impl Network {
+        fn new(lose: Option<(&str, &str)>) -> Self {
+            let line = [
+                ("n0", vec!["n1"]),
+                ("n1", vec!["n0", "n2"]),
+                ("n2", vec!["n1"]),
+            ];
+            let trees = line
+                .into_iter()
+                .map(|(id, peers)| {
+                    let peers = peers.into_iter().map(String::from).collect::<Vec<_>>();
+                    let tree = Plumtree::new(id, &peers)
+                        .with_lazy_interval(Duration::ZERO)
+                        .with_graft_timeout(Duration::ZERO);
+
+                    (id.to_string(), tree)
+                })
+                .collect();
+
+            Self {
+                trees,
+                sent: VecDeque::new(),
+                lose: lose.map(|(src, dest)| (src.to_string(), dest.to_string())),
+            }
+        }
+
+        fn send(&mut self, src: &str, outgoing: Vec<(String, Outgoing<usize>)>) {
+            for (dest, outgoing) in outgoing {
+                self.sent.push_back((src.to_string(), dest, outgoing));
+            }
+        }
+
+        fn run(&mut self) {
+            while let Some((src, dest, outgoing)) = self.sent.pop_front() {
+                let tree = self.trees.get_mut(&dest).unwrap();
+                match outgoing {
+                    Outgoing::Gossip { root, values } => {
+                        if self.lose.as_ref() == Some(&(src.clone(), dest.clone())) {
+                            self.lose = None;
+                            continue;
+                        }
+
+                        let outgoing = tree.gossip(&src, &root, &values);
+                        self.send(&dest, outgoing);
+                    }
+                    // Acknowledgements and graft replies go straight back.
+                    Outgoing::IHave(announced) => {
+                        tree.ihave(&src, &announced);
+                        let values = announced.into_iter().map(|(_, value)| value);
+                        let tree = self.trees.get_mut(&src).unwrap();
+                        tree.ihave_ok(&dest, &values.collect::<Vec<_>>());
+                    }
+                    Outgoing::Graft { root, values } => {
+                        let values = tree.graft(&src, &root, &values);
+                        let tree = self.trees.get_mut(&src).unwrap();
+                        let outgoing = tree.graft_ok(&dest, &root, &values);
+                        self.send(&src, outgoing);
+                    }
+                    Outgoing::Prune { root } => tree.prune(&src, &root),
+                }
+            }
+        }
+
+        fn tick(&mut self) {
+            for id in ["n0", "n1", "n2"] {
+                let outgoing = self.trees.get_mut(id).unwrap().tick();
+                self.send(id, outgoing);
+            }
+
+            self.run();
+        }
+
+        fn received(&self, id: &str) -> &HashSet<usize> {
+            self.trees[id].received()
+        }
+    }
+
+    #[test]
+    fn a_lost_eager_push_is_announced_and_grafted() {
+        let mut network = Network::new(Some(("n0", "n1")));
+        let outgoing = network.trees.get_mut("n0").unwrap().broadcast(7);
+        network.send("n0", outgoing);
+        network.run();
+        assert!(network.received("n1").is_empty());
+
+        // n0 announces 7 to n1, which grafts it on the next tick and pushes
+        // it on to n2 once the graft is answered.
+        network.tick();
+        network.tick();
+        assert!(network.received("n1").contains(&7));
+        assert!(network.received("n2").contains(&7));
+    }
+
+    #[test]
+    fn announcements_stop_once_acknowledged() {
+        let mut network = Network::new(None);
+        let outgoing = network.trees.get_mut("n0").unwrap().broadcast(7);
+        network.send("n0", outgoing);
+        network.run();
+        network.tick();
+
+        for tree in network.trees.values_mut() {
+            assert_eq!(tree.tick(), vec![]);
+        }
+    }
+    #[test]
+    fn graft_replies_never_prune() {
+        let peers = ["n0".to_string(), "n2".to_string()];
+        let mut tree = Plumtree::new("n1", &peers);
+        tree.gossip("n0", "n0", &[1]);
+
+        // n2 grafted 1 too, but it arrived from n0 first: the reply is a
+        // duplicate, and still leaves n2 eager.
+        assert_eq!(tree.graft_ok("n2", "n0", &[1]), vec![]);
+        assert_eq!(
+            tree.gossip("n0", "n0", &[2]),
+            vec![(
+                "n2".to_string(),
+                Outgoing::Gossip {
+                    root: "n0".to_string(),
+                    values: vec![2]
+                }
+            )]
+        );
+
+        // An eager push of nothing new prunes the link that sent it.
+        assert_eq!(
+            tree.gossip("n2", "n0", &[2]),
+            vec![(
+                "n2".to_string(),
+                Outgoing::Prune {
+                    root: "n0".to_string()
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn grafts_answer_only_values_the_node_has() {
+        let mut tree = Plumtree::new("n1", &["n0".to_string()]);
+        tree.broadcast(1);
+        assert_eq!(tree.graft("n0", "n1", &[1, 2]), vec![1]);
+    }
+}
@@ -1,30 +1,131 @@
-use std::io::{BufRead, Read, StdoutLock};
+use std::{
+    collections::VecDeque,
+    io::{BufRead, StdoutLock},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    time::{Duration, Instant},
+};
 
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
 
 use crate::{
+    batch::{Batches, Coalesce},
+    config,
     error::MaelstromError,
+    link::{Frame, Links},
     message::{InitializationRequest, InitializationResponse, Message, MessageBody},
     node::MaelstromNode,
+    trace::{Recorder, Replayer, Step, TraceEvent},
 };
 
+const RECORD_VAR: &str = "MAELSTROM_RECORD";
+const REPLAY_VAR: &str = "MAELSTROM_REPLAY";
+const LINK_RETRANSMIT: Duration = Duration::from_millis(250);
+
+struct Timer {
+    interval: Duration,
+    next: Instant,
+}
+
+enum Input {
+    Line(String),
+    Timer(usize),
+}
+
+enum Inputs {
+    Live(Receiver<String>),
+    Replay(Replayer, Option<Step>),
+}
+
+enum Output {
+    Stdout(StdoutLock<'static>),
+    Captured(Vec<Value>),
+}
+
 pub struct Service {
+    node_id: String,
     outbox_id: usize,
-    output: StdoutLock<'static>,
+    output: Output,
+    inputs: Option<Inputs>,
+    deferred: VecDeque<String>,
+    timers: Vec<Timer>,
+    started: Instant,
+    record_path: Option<String>,
+    recorder: Option<Recorder>,
+    links: Links,
+    // The timer that retransmits on reliable links, registered on their first
+    // use.
+    link_timer: Option<usize>,
+    link_retransmit: Duration,
+    // Outbound peer payloads held back to be coalesced, once batching is
+    // turned on.
+    batches: Option<Batches>,
+    batch_timer: Option<usize>,
+}
+
+impl Default for Service {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Service {
     pub fn new() -> Self {
         Self {
+            node_id: String::new(),
             outbox_id: 1,
-            output: std::io::stdout().lock(),
+            output: Output::Stdout(std::io::stdout().lock()),
+            inputs: None,
+            deferred: VecDeque::new(),
+            timers: vec![],
+            started: Instant::now(),
+            record_path: std::env::var(RECORD_VAR).ok(),
+            recorder: None,
+            links: Links::default(),
+            link_timer: None,
+            link_retransmit: LINK_RETRANSMIT,
+            batches: None,
+            batch_timer: None,
         }
     }
 
+    pub fn node_id(&self) -> &str {
+        &self.node_id
+    }
+
     pub fn outbox_id(&self) -> usize {
         self.outbox_id
     }
 
+    // Registers a periodic timer and returns its id, which is handed back to
+    // `MaelstromNode::handle_timer` every time it fires.
+    pub fn every(&mut self, interval: Duration) -> usize {
+        self.timers.push(Timer {
+            interval,
+            next: Instant::now() + interval,
+        });
+
+        self.timers.len() - 1
+    }
+
+    fn send<P: Serialize>(&mut self, message: Message<P>) -> Result<(), MaelstromError> {
+        self.outbox_id += 1;
+        if let Some(recorder) = &mut self.recorder {
+            let message = serde_json::to_value(&message).map_err(|_| MaelstromError::IOError)?;
+            recorder.record(TraceEvent::Outbound { message })?;
+        }
+
+        match &mut self.output {
+            Output::Stdout(output) => message.write_to(output),
+            Output::Captured(messages) => {
+                let message =
+                    serde_json::to_value(&message).map_err(|_| MaelstromError::IOError)?;
+                messages.push(message);
+                Ok(())
+            }
+        }
+    }
+
     pub fn respond_to<T, U: Serialize>(
         &mut self,
         message: &Message<T>,
@@ -40,8 +141,7 @@ impl Service {
             },
         };
 
-        self.outbox_id += 1;
-        message.write_to(&mut self.output)
+        self.send(message)
     }
 
     pub fn peer_rpc<T: Serialize>(
@@ -60,39 +160,370 @@ impl Service {
             },
         };
 
-        self.outbox_id += 1;
-        message.write_to(&mut self.output)
+        self.send(message)
+    }
+
+    // Sends `payload` to the peer `dest` over a reliable link. It is resent
+    // until acknowledged, and `dest` hands it to `MaelstromNode::handle_peer`
+    // exactly once and in the order it was sent; replies to it are sent back
+    // the same way.
+    pub fn send_reliable<T: Serialize>(
+        &mut self,
+        dest: &str,
+        payload: T,
+    ) -> Result<(), MaelstromError> {
+        let data = serde_json::to_value(payload).map_err(|_| MaelstromError::IOError)?;
+        if self.link_timer.is_none() {
+            self.link_timer = Some(self.every(self.link_retransmit));
+        }
+
+        let frame = self.links.send(dest, data);
+        self.send_frame(dest, frame)
+    }
+
+    // Sets how often unacknowledged payloads on reliable links are resent.
+    // Peers that stay silent are resent to less often than this.
+    pub fn retransmit_every(&mut self, interval: Duration) {
+        self.link_retransmit = interval;
+        if let Some(timer) = self.link_timer {
+            self.timers[timer] = Timer {
+                interval,
+                next: Instant::now() + interval,
+            };
+        }
+    }
+
+    fn send_frame(&mut self, dest: &str, frame: Frame) -> Result<(), MaelstromError> {
+        self.peer_rpc(self.node_id.clone(), dest.to_string(), frame)
+    }
+
+    fn handle_frame<N: MaelstromNode>(
+        &mut self,
+        node: &mut N,
+        message: Message<Frame>,
+    ) -> Result<(), MaelstromError> {
+        let Message { src, dest, body } = message;
+        match body.payload {
+            Frame::LinkAck { ack } => self.links.ack(&src, ack),
+            Frame::LinkData { seq, data } => {
+                let (delivered, ack) = self.links.receive(&src, seq, data);
+                self.send_frame(&src, ack)?;
+                for data in delivered {
+                    let payload = serde_json::from_value(data)
+                        .map_err(|_| MaelstromError::MessageParseError)?;
+                    let message = Message::new(src.clone(), dest.clone(), None, None, payload);
+                    if let Some(reply) = node.handle_peer(&message, self)? {
+                        self.send_reliable(&src, reply)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn retransmit(&mut self) -> Result<(), MaelstromError> {
+        for (peer, frame) in self.links.retransmit() {
+            self.send_frame(&peer, frame)?;
+        }
+
+        Ok(())
+    }
+
+    // Turns on batching for `send_batched`: payloads to a destination are held
+    // for up to `window` and coalesced, and sent early once `max_batch` of
+    // them have been merged. Calling it again changes both, sending whatever
+    // is held first.
+    pub fn batch(&mut self, window: Duration, max_batch: usize) -> Result<(), MaelstromError> {
+        self.flush_batches()?;
+        match self.batch_timer {
+            Some(timer) => {
+                self.timers[timer] = Timer {
+                    interval: window,
+                    next: Instant::now() + window,
+                };
+            }
+            None => self.batch_timer = Some(self.every(window)),
+        }
+
+        self.batches = Some(Batches::new(max_batch));
+        Ok(())
+    }
+
+    // Sends `payload` to the peer `dest`, coalesced with the other payloads
+    // sent to it within the batching window. Without batching turned on it is
+    // sent straight away.
+    pub fn send_batched<P>(&mut self, dest: &str, payload: P) -> Result<(), MaelstromError>
+    where
+        P: Coalesce + Serialize + 'static,
+    {
+        let Some(batches) = &mut self.batches else {
+            return self.peer_rpc(self.node_id.clone(), dest.to_string(), payload);
+        };
+
+        for payload in batches.push(dest, payload)? {
+            self.peer_rpc(self.node_id.clone(), dest.to_string(), payload)?;
+        }
+
+        Ok(())
+    }
+
+    fn flush_batches(&mut self) -> Result<(), MaelstromError> {
+        let Some(batches) = &mut self.batches else {
+            return Ok(());
+        };
+
+        for (dest, payload) in batches.flush()? {
+            self.peer_rpc(self.node_id.clone(), dest, payload)?;
+        }
+
+        Ok(())
+    }
+
+    // Sends `payload` to `dest` and blocks until the matching reply arrives or
+    // `timeout` elapses. Anything else received in the meantime is handled
+    // once the current message or timer is done.
+    pub fn rpc<T: Serialize, R: DeserializeOwned>(
+        &mut self,
+        dest: &str,
+        payload: T,
+        timeout: Duration,
+    ) -> Result<R, MaelstromError> {
+        let message_id = self.outbox_id;
+        self.peer_rpc(self.node_id.clone(), dest.to_string(), payload)?;
+
+        let line = self
+            .await_reply(message_id, Instant::now() + timeout)?
+            .ok_or(MaelstromError::Timeout)?;
+        let reply: Message<R> = line.parse()?;
+        Ok(reply.body.payload)
+    }
+
+    fn await_reply(
+        &mut self,
+        message_id: usize,
+        deadline: Instant,
+    ) -> Result<Option<String>, MaelstromError> {
+        let is_reply = |line: &str| {
+            line.parse::<Message<Value>>()
+                .is_ok_and(|message| message.in_reply_to() == Some(message_id))
+        };
+
+        // Replies are recorded as the node consumes them, so a recording holds
+        // the reply as the very next step if it arrived in time.
+        self.finish_step();
+        match &mut self.inputs {
+            Some(Inputs::Replay(replayer, current)) => match replayer.peek() {
+                Some(Step {
+                    event: TraceEvent::Inbound { line },
+                    ..
+                }) if is_reply(line) => {
+                    let line = line.clone();
+                    *current = replayer.next_step();
+                    Ok(Some(line))
+                }
+                _ => Ok(None),
+            },
+            Some(Inputs::Live(lines)) => loop {
+                let now = Instant::now();
+                if now >= deadline {
+                    return Ok(None);
+                }
+
+                match lines.recv_timeout(deadline - now) {
+                    Ok(line) if is_reply(&line) => {
+                        if let Some(recorder) = &mut self.recorder {
+                            recorder.record(TraceEvent::Inbound { line: line.clone() })?;
+                        }
+
+                        return Ok(Some(line));
+                    }
+                    Ok(line) => self.deferred.push_back(line),
+                    Err(RecvTimeoutError::Timeout) => return Ok(None),
+                    Err(RecvTimeoutError::Disconnected) => return Err(MaelstromError::IOError),
+                }
+            },
+            None => Err(MaelstromError::IOError),
+        }
+    }
+
+    fn open_inputs(&mut self) -> Result<(), MaelstromError> {
+        let inputs = match std::env::var(REPLAY_VAR) {
+            Ok(path) => {
+                self.output = Output::Captured(vec![]);
+                self.record_path = None;
+                Inputs::Replay(Replayer::open(&path)?, None)
+            }
+            Err(_) => {
+                let (tx, rx) = mpsc::channel();
+                std::thread::spawn(move || {
+                    for line in std::io::stdin().lock().lines() {
+                        let Ok(line) = line else {
+                            break;
+                        };
+
+                        if tx.send(line).is_err() {
+                            break;
+                        }
+                    }
+                });
+
+                Inputs::Live(rx)
+            }
+        };
+
+        self.inputs = Some(inputs);
+        Ok(())
+    }
+
+    fn next_input(&mut self) -> Option<Input> {
+        // Lines set aside before init or while awaiting a reply come first,
+        // whether live or replaying.
+        if let Some(line) = self.deferred.pop_front() {
+            return Some(Input::Line(line));
+        }
+
+        match self.inputs.as_mut()? {
+            Inputs::Replay(replayer, current) => {
+                let step = replayer.next_step()?;
+                let input = match &step.event {
+                    TraceEvent::Inbound { line } => Input::Line(line.clone()),
+                    TraceEvent::Timer { timer } => Input::Timer(*timer),
+                    TraceEvent::Outbound { .. } => return None,
+                };
+
+                *current = Some(step);
+                Some(input)
+            }
+            Inputs::Live(lines) => loop {
+                let next_timer = self
+                    .timers
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, timer)| timer.next);
+
+                let Some((id, timer)) = next_timer else {
+                    return lines.recv().ok().map(Input::Line);
+                };
+
+                let now = Instant::now();
+                if timer.next <= now {
+                    let timer = &mut self.timers[id];
+                    timer.next = now + timer.interval;
+                    return Some(Input::Timer(id));
+                }
+
+                match lines.recv_timeout(timer.next - now) {
+                    Ok(line) => return Some(Input::Line(line)),
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => return None,
+                }
+            },
+        }
+    }
+
+    fn finish_step(&mut self) {
+        let (Some(Inputs::Replay(replayer, current)), Output::Captured(actual)) =
+            (&mut self.inputs, &mut self.output)
+        else {
+            return;
+        };
+
+        if let Some(step) = current.take() {
+            replayer.report(&step, actual);
+        }
+
+        actual.clear();
+    }
+
+    fn handle_line<N: MaelstromNode>(
+        &mut self,
+        node: &mut N,
+        line: String,
+    ) -> Result<(), MaelstromError> {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(TraceEvent::Inbound { line: line.clone() })?;
+        }
+
+        if let Ok(message) = line.parse::<Message<Frame>>() {
+            self.handle_frame(node, message)?;
+        } else if let Ok(message) = line.parse::<Message<N::InputPayload>>() {
+            if let Some(payload) = node.handle(&message, self)? {
+                self.respond_to(&message, payload)?;
+            }
+        } else if let Ok(message) = line.parse::<Message<N::PeerPayload>>() {
+            if let Some(payload) = node.handle_peer(&message, self)? {
+                self.respond_to(&message, payload)?;
+            }
+        } else if line
+            .parse::<Message<Value>>()
+            .is_ok_and(|message| message.in_reply_to().is_some())
+        {
+            // A late reply to an rpc that already timed out.
+        } else {
+            return Err(MaelstromError::MessageParseError);
+        }
+
+        Ok(())
     }
 
     pub fn run<N: MaelstromNode>(&mut self) -> Result<(), MaelstromError> {
-        let mut input = std::io::stdin().lock();
+        let config = config::load::<N::Config>()?;
+        self.open_inputs()?;
+
+        // Peers may message this node before Maelstrom's init reaches it, so
+        // anything that comes first is handled once the node exists.
+        let mut early = vec![];
+        let (line, init_message) = loop {
+            let Some(Input::Line(line)) = self.next_input() else {
+                return Err(MaelstromError::IOError);
+            };
 
-        let line = input
-            .by_ref()
-            .lines()
-            .next()
-            .expect("an initialization message")
-            .map_err(|_| MaelstromError::IOError)?;
+            match line.parse::<Message<InitializationRequest>>() {
+                Ok(init_message) => break (line, init_message),
+                Err(_) => early.push(line),
+            }
+        };
 
-        let init_message: Message<InitializationRequest> = line
-            .parse()
-            .map_err(|_| MaelstromError::MessageParseError)?;
+        self.deferred.extend(early);
 
-        let mut node = N::new(&init_message);
+        let InitializationRequest::Init { id, .. } = init_message.payload();
+        self.node_id = id.clone();
+        if let Some(path) = &self.record_path {
+            let mut recorder = Recorder::create(path, id, self.started)?;
+            recorder.record(TraceEvent::Inbound { line })?;
+            self.recorder = Some(recorder);
+        }
+
+        let mut node = N::new(&init_message, config);
         self.respond_to(&init_message, InitializationResponse::InitOk)?;
+        node.start(self)?;
+        self.finish_step();
 
-        for line in input.by_ref().lines() {
-            let line = line.map_err(|_| MaelstromError::IOError)?;
-            if let Ok(message) = line.parse::<Message<N::InputPayload>>() {
-                if let Some(payload) = node.handle(&message, self)? {
-                    self.respond_to(&message, payload)?;
-                }
-            } else if let Ok(message) = line.parse::<Message<N::PeerPayload>>() {
-                if let Some(payload) = node.handle_peer(&message, self)? {
-                    self.respond_to(&message, payload)?;
+        while let Some(input) = self.next_input() {
+            match input {
+                Input::Line(line) => self.handle_line(&mut node, line)?,
+                Input::Timer(timer) => {
+                    if let Some(recorder) = &mut self.recorder {
+                        recorder.record(TraceEvent::Timer { timer })?;
+                    }
+
+                    if self.link_timer == Some(timer) {
+                        self.retransmit()?;
+                    } else if self.batch_timer == Some(timer) {
+                        self.flush_batches()?;
+                    } else {
+                        node.handle_timer(timer, self)?;
+                    }
                 }
-            } else {
-                return Err(MaelstromError::MessageParseError);
+            }
+
+            self.finish_step();
+        }
+
+        if let Some(Inputs::Replay(replayer, _)) = &self.inputs {
+            if replayer.mismatches() > 0 {
+                return Err(MaelstromError::ReplayDiverged);
             }
         }
 
@@ -0,0 +1,306 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// Who a node talks to. Computed topologies only depend on the node ids, which
+// every node gets in the same order, so all nodes agree on the graph without
+// exchanging anything; every edge is symmetric.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Topology {
+    // Every node with every other.
+    Full,
+    // The topology Maelstrom suggests in its `topology` message.
+    Maelstrom,
+    // A breadth-first spanning tree of Maelstrom's suggestion, rooted at the
+    // first node.
+    SpanningTree,
+    // A tree where each node has up to k children.
+    Tree(usize),
+    // A square-ish grid, each node linked to the nodes beside it.
+    Grid,
+    // A ring, plus chords to the nodes 2, 4, 8, ... positions further on.
+    RingWithChords,
+    // A random graph of degree k, the same on every node. k is at least 2,
+    // since a single matching leaves the graph disconnected.
+    RandomRegular(usize),
+}
+
+impl Topology {
+    // Parses `full`, `maelstrom`, `spanning-tree`, `tree:K`, `grid`,
+    // `ring-with-chords` or `random:K`.
+    pub fn parse(name: &str) -> Option<Self> {
+        let (kind, degree) = match name.split_once(':') {
+            Some((kind, degree)) => (kind, Some(degree.parse().ok().filter(|&k| k > 0)?)),
+            None => (name, None),
+        };
+
+        match (kind, degree) {
+            ("full", None) => Some(Self::Full),
+            ("maelstrom", None) => Some(Self::Maelstrom),
+            ("spanning-tree", None) => Some(Self::SpanningTree),
+            ("tree", Some(k)) => Some(Self::Tree(k)),
+            ("grid", None) => Some(Self::Grid),
+            ("ring-with-chords", None) => Some(Self::RingWithChords),
+            ("random", Some(k)) if k >= 2 => Some(Self::RandomRegular(k)),
+            _ => None,
+        }
+    }
+
+    // Whether the topology needs Maelstrom's suggestion to be computed.
+    pub fn is_suggested(&self) -> bool {
+        matches!(self, Self::Maelstrom | Self::SpanningTree)
+    }
+
+    pub fn neighbors(
+        &self,
+        id: &str,
+        node_ids: &[String],
+        suggested: &HashMap<String, Vec<String>>,
+    ) -> Vec<String> {
+        let Some(index) = node_ids.iter().position(|node| node == id) else {
+            return vec![];
+        };
+
+        let n = node_ids.len();
+        let indices = match self {
+            Self::Full => (0..n).collect(),
+            Self::Maelstrom => {
+                return suggested
+                    .get(id)
+                    .into_iter()
+                    .flatten()
+                    .filter(|&node| node != id)
+                    .cloned()
+                    .collect();
+            }
+            Self::SpanningTree => {
+                return spanning_tree(node_ids, suggested)
+                    .remove(id)
+                    .unwrap_or_default()
+            }
+            Self::Tree(k) => {
+                // Any k may be asked for, so the children are found without
+                // overflowing past the last node.
+                let first = k
+                    .checked_mul(index)
+                    .and_then(|before| before.checked_add(1))
+                    .unwrap_or(n);
+                let mut indices = (first..first.saturating_add(*k).min(n)).collect::<Vec<_>>();
+                if index > 0 {
+                    indices.push((index - 1) / k);
+                }
+
+                indices
+            }
+            Self::Grid => {
+                let columns = (n as f64).sqrt().ceil() as usize;
+                let mut indices = vec![index + columns];
+                if index % columns + 1 < columns {
+                    indices.push(index + 1);
+                }
+
+                if index % columns > 0 {
+                    indices.push(index - 1);
+                }
+
+                if index >= columns {
+                    indices.push(index - columns);
+                }
+
+                indices.into_iter().filter(|&other| other < n).collect()
+            }
+            Self::RingWithChords => {
+                let offsets = (0..)
+                    .map(|power| 1 << power)
+                    .take_while(|&offset| offset < n);
+                offsets
+                    .flat_map(|offset| [(index + offset) % n, (index + n - offset) % n])
+                    .collect()
+            }
+            Self::RandomRegular(k) => random_regular(n, *k)
+                .into_iter()
+                .filter_map(|(a, b)| match (a == index, b == index) {
+                    (true, _) => Some(b),
+                    (_, true) => Some(a),
+                    _ => None,
+                })
+                .collect(),
+        };
+
+        let mut seen = HashSet::new();
+        indices
+            .into_iter()
+            .filter(|&other| other != index && seen.insert(other))
+            .map(|other| node_ids[other].clone())
+            .collect()
+    }
+}
+
+fn spanning_tree(
+    node_ids: &[String],
+    suggested: &HashMap<String, Vec<String>>,
+) -> HashMap<String, Vec<String>> {
+    let mut tree: HashMap<String, Vec<String>> = HashMap::new();
+    let Some(root) = node_ids.first() else {
+        return tree;
+    };
+
+    let mut visited = HashSet::from([root.clone()]);
+    let mut queue = VecDeque::from([root.clone()]);
+    while let Some(node) = queue.pop_front() {
+        for next in suggested.get(&node).into_iter().flatten() {
+            if visited.insert(next.clone()) {
+                tree.entry(node.clone()).or_default().push(next.clone());
+                tree.entry(next.clone()).or_default().push(node.clone());
+                queue.push_back(next.clone());
+            }
+        }
+    }
+
+    tree
+}
+
+// The union of k/2 random Hamiltonian cycles, plus a random perfect matching
+// when k is odd, so nearly every node ends up with degree k. The generator is
+// seeded with a constant so that every node builds the same graph.
+fn random_regular(n: usize, k: usize) -> HashSet<(usize, usize)> {
+    let mut state = 0x9e3779b97f4a7c15_u64;
+    let mut next = |bound: usize| {
+        // splitmix64
+        state = state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        ((z ^ (z >> 31)) % bound as u64) as usize
+    };
+
+    let mut shuffled = |n: usize| {
+        let mut order = (0..n).collect::<Vec<_>>();
+        for i in (1..n).rev() {
+            order.swap(i, next(i + 1));
+        }
+
+        order
+    };
+
+    let edge = |a: usize, b: usize| (a.min(b), a.max(b));
+    let mut edges = HashSet::new();
+    for _ in 0..k / 2 {
+        let order = shuffled(n);
+        for i in 0..n {
+            edges.insert(edge(order[i], order[(i + 1) % n]));
+        }
+    }
+
+    if k % 2 == 1 {
+        let order = shuffled(n);
+        for pair in order.chunks_exact(2) {
+            edges.insert(edge(pair[0], pair[1]));
+        }
+    }
+
+    edges.retain(|(a, b)| a != b);
+    edges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(n: usize) -> Vec<String> {
+        (0..n).map(|index| format!("n{index}")).collect()
+    }
+
+    fn graph(
+        topology: Topology,
+        node_ids: &[String],
+        suggested: &HashMap<String, Vec<String>>,
+    ) -> HashMap<String, Vec<String>> {
+        node_ids
+            .iter()
+            .map(|id| (id.clone(), topology.neighbors(id, node_ids, suggested)))
+            .collect()
+    }
+
+    fn is_connected(graph: &HashMap<String, Vec<String>>, node_ids: &[String]) -> bool {
+        let Some(root) = node_ids.first() else {
+            return true;
+        };
+
+        let mut visited = HashSet::from([root]);
+        let mut queue = VecDeque::from([root]);
+        while let Some(node) = queue.pop_front() {
+            for next in &graph[node] {
+                if visited.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        visited.len() == node_ids.len()
+    }
+
+    #[test]
+    fn every_kind_is_symmetric_and_connected() {
+        let kinds = [
+            Topology::Full,
+            Topology::Maelstrom,
+            Topology::SpanningTree,
+            Topology::Tree(1),
+            Topology::Tree(2),
+            Topology::Tree(4),
+            Topology::Tree(usize::MAX),
+            Topology::Grid,
+            Topology::RingWithChords,
+            Topology::RandomRegular(2),
+            Topology::RandomRegular(3),
+            Topology::RandomRegular(5),
+        ];
+
+        for n in 1..=30 {
+            let node_ids = ids(n);
+            // Maelstrom suggests a grid.
+            let suggested = graph(Topology::Grid, &node_ids, &HashMap::new());
+            for topology in kinds {
+                let graph = graph(topology, &node_ids, &suggested);
+                for (node, neighbors) in &graph {
+                    assert!(
+                        !neighbors.contains(node),
+                        "{topology:?} links {node} to itself"
+                    );
+                    for neighbor in neighbors {
+                        assert!(
+                            graph[neighbor].contains(node),
+                            "{topology:?} on {n} nodes links {node} to {neighbor} but not back"
+                        );
+                    }
+                }
+
+                assert!(
+                    is_connected(&graph, &node_ids),
+                    "{topology:?} on {n} nodes is disconnected"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn trees_have_up_to_k_children() {
+        let node_ids = ids(10);
+        let neighbors = |id| Topology::Tree(3).neighbors(id, &node_ids, &HashMap::new());
+        assert_eq!(neighbors("n0"), vec!["n1", "n2", "n3"]);
+        assert_eq!(neighbors("n2"), vec!["n7", "n8", "n9", "n0"]);
+        assert_eq!(neighbors("n9"), vec!["n2"]);
+    }
+
+    #[test]
+    fn parses_names() {
+        assert_eq!(Topology::parse("full"), Some(Topology::Full));
+        assert_eq!(Topology::parse("tree:4"), Some(Topology::Tree(4)));
+        assert_eq!(
+            Topology::parse("random:3"),
+            Some(Topology::RandomRegular(3))
+        );
+        for name in ["tree", "tree:0", "random:1", "random:x", "grid:2", "star"] {
+            assert_eq!(Topology::parse(name), None, "{name} should not parse");
+        }
+    }
+}
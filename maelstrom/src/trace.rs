@@ -0,0 +1,146 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    time::Instant,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::MaelstromError;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TraceEvent {
+    Inbound { line: String },
+    Outbound { message: Value },
+    Timer { timer: usize },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceRecord {
+    pub time: u64,
+    #[serde(flatten)]
+    pub event: TraceEvent,
+}
+
+pub struct Recorder {
+    started: Instant,
+    output: BufWriter<File>,
+}
+
+impl Recorder {
+    // `{node}` in the path is replaced with the node id, so that every node of
+    // a cluster sharing one binary can record to its own file.
+    pub fn create(path: &str, node_id: &str, started: Instant) -> Result<Self, MaelstromError> {
+        let path = path.replace("{node}", node_id);
+        let file = File::create(path).map_err(|_| MaelstromError::IOError)?;
+        Ok(Self {
+            started,
+            output: BufWriter::new(file),
+        })
+    }
+
+    pub fn record(&mut self, event: TraceEvent) -> Result<(), MaelstromError> {
+        let record = TraceRecord {
+            time: self.started.elapsed().as_nanos() as u64,
+            event,
+        };
+
+        serde_json::to_writer(&mut self.output, &record).map_err(|_| MaelstromError::IOError)?;
+        self.output
+            .write_all(b"\n")
+            .map_err(|_| MaelstromError::IOError)?;
+        self.output.flush().map_err(|_| MaelstromError::IOError)
+    }
+}
+
+pub struct Step {
+    pub time: u64,
+    pub event: TraceEvent,
+    pub expected: Vec<Value>,
+}
+
+// Splits a recording into steps: every inbound line or timer fire together
+// with the outbound messages the node produced while handling it.
+pub struct Replayer {
+    steps: VecDeque<Step>,
+    index: usize,
+    mismatches: usize,
+}
+
+impl Replayer {
+    pub fn open(path: &str) -> Result<Self, MaelstromError> {
+        let file = File::open(path).map_err(|_| MaelstromError::IOError)?;
+        let mut steps: VecDeque<Step> = VecDeque::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|_| MaelstromError::IOError)?;
+            let record: TraceRecord =
+                serde_json::from_str(&line).map_err(|_| MaelstromError::MessageParseError)?;
+
+            match record.event {
+                TraceEvent::Outbound { message } => match steps.back_mut() {
+                    Some(step) => step.expected.push(message),
+                    None => return Err(MaelstromError::MessageParseError),
+                },
+                event => steps.push_back(Step {
+                    time: record.time,
+                    event,
+                    expected: vec![],
+                }),
+            }
+        }
+
+        Ok(Self {
+            steps,
+            index: 0,
+            mismatches: 0,
+        })
+    }
+
+    pub fn next_step(&mut self) -> Option<Step> {
+        self.index += 1;
+        self.steps.pop_front()
+    }
+
+    pub fn peek(&self) -> Option<&Step> {
+        self.steps.front()
+    }
+
+    pub fn mismatches(&self) -> usize {
+        self.mismatches
+    }
+
+    pub fn report(&mut self, step: &Step, actual: &[Value]) {
+        let label = match &step.event {
+            TraceEvent::Inbound { line } => format!("inbound {line}"),
+            TraceEvent::Timer { timer } => format!("timer {timer}"),
+            TraceEvent::Outbound { .. } => unreachable!("outbound events are folded into steps"),
+        };
+
+        let diverged = step.expected != actual;
+        if diverged {
+            self.mismatches += 1;
+        }
+
+        println!(
+            "step {} @ {}ms{}: {label}",
+            self.index,
+            step.time / 1_000_000,
+            if diverged { " [DIVERGED]" } else { "" }
+        );
+
+        for message in &step.expected {
+            let marker = if actual.contains(message) { ' ' } else { '-' };
+            println!("  {marker} {message}");
+        }
+
+        for message in actual
+            .iter()
+            .filter(|message| !step.expected.contains(message))
+        {
+            println!("  + {message}");
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+// A micro-operation of a Maelstrom `txn` request. On the wire these are
+// heterogeneous arrays: `["r", key, null]` for a read, with the value filled
+// in on the way back, and `["w", key, value]` for a write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MicroOp {
+    Read { key: usize, value: Option<usize> },
+    Write { key: usize, value: usize },
+}
+
+impl MicroOp {
+    pub fn key(&self) -> usize {
+        match self {
+            Self::Read { key, .. } | Self::Write { key, .. } => *key,
+        }
+    }
+}
+
+impl Serialize for MicroOp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            Self::Read { key, value } => ("r", key, value).serialize(serializer),
+            Self::Write { key, value } => ("w", key, Some(value)).serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for MicroOp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (function, key, value) = <(String, usize, Option<usize>)>::deserialize(deserializer)?;
+        match (function.as_str(), value) {
+            ("r", value) => Ok(Self::Read { key, value }),
+            ("w", Some(value)) => Ok(Self::Write { key, value }),
+            ("w", None) => Err(D::Error::custom("write micro-op without a value")),
+            (function, _) => Err(D::Error::custom(format!(
+                "unknown micro-op function {function:?}"
+            ))),
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub enum SimulatorError {
+    IOError,
+    SpawnError(String),
+    InitTimeout(String),
+    NodeExited(String),
+    MessageParseError(String),
+}
+
+impl Display for SimulatorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IOError => write!(f, "[simulator error] - io error"),
+            Self::SpawnError(binary) => {
+                write!(f, "[simulator error] - failed to spawn {binary}")
+            }
+            Self::InitTimeout(node) => {
+                write!(f, "[simulator error] - {node} did not acknowledge init")
+            }
+            Self::NodeExited(node) => write!(f, "[simulator error] - {node} exited"),
+            Self::MessageParseError(line) => {
+                write!(f, "[simulator error] - failed to parse node output: {line}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SimulatorError {}
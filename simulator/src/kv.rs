@@ -0,0 +1,231 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use maelstrom::{
+    error::{KEY_DOES_NOT_EXIST, PRECONDITION_FAILED},
+    kv::{KvKind, KvRequest, KvResponse},
+};
+use rand::prelude::*;
+use serde_json::Value;
+
+// How far behind the latest version a seq-kv read may land.
+const SEQ_STALENESS: u64 = 32;
+const LWW_REPLICAS: usize = 3;
+const LWW_MAX_SKEW: Duration = Duration::from_millis(5);
+const LWW_MAX_REPLICATION_DELAY: Duration = Duration::from_millis(50);
+
+// An in-process stand-in for one of Maelstrom's key-value services, answering
+// requests on behalf of `client`.
+pub trait KvStore {
+    fn handle(&mut self, client: &str, request: KvRequest) -> KvResponse;
+}
+
+pub fn store(kind: KvKind) -> Box<dyn KvStore> {
+    match kind {
+        KvKind::Seq => Box::<SeqKv>::default(),
+        KvKind::Lin => Box::<LinKv>::default(),
+        KvKind::Lww => Box::new(LwwKv::new(LWW_REPLICAS)),
+    }
+}
+
+fn key_of(request: &KvRequest) -> String {
+    match request {
+        KvRequest::Read { key } | KvRequest::Write { key, .. } | KvRequest::Cas { key, .. } => {
+            key.to_string()
+        }
+    }
+}
+
+// Applies `request` to the current value of its key, returning the reply and
+// the value to store if the request changes it.
+fn apply(current: Option<&Value>, request: KvRequest) -> (KvResponse, Option<Value>) {
+    match (request, current) {
+        (KvRequest::Read { .. }, Some(value)) => (
+            KvResponse::ReadOk {
+                value: value.clone(),
+            },
+            None,
+        ),
+        (KvRequest::Read { .. }, None) => (
+            KvResponse::error(KEY_DOES_NOT_EXIST, "key does not exist"),
+            None,
+        ),
+        (KvRequest::Write { value, .. }, _) => (KvResponse::WriteOk, Some(value)),
+        (
+            KvRequest::Cas {
+                create_if_not_exists: true,
+                to,
+                ..
+            },
+            None,
+        ) => (KvResponse::CasOk, Some(to)),
+        (KvRequest::Cas { .. }, None) => (
+            KvResponse::error(KEY_DOES_NOT_EXIST, "key does not exist"),
+            None,
+        ),
+        (KvRequest::Cas { from, to, .. }, Some(value)) if *value == from => {
+            (KvResponse::CasOk, Some(to))
+        }
+        (KvRequest::Cas { from, .. }, Some(value)) => (
+            KvResponse::error(
+                PRECONDITION_FAILED,
+                format!("current value {value} is not {from}"),
+            ),
+            None,
+        ),
+    }
+}
+
+// A single copy of the data, updated atomically as each request arrives.
+#[derive(Debug, Default)]
+pub struct LinKv {
+    data: HashMap<String, Value>,
+}
+
+impl KvStore for LinKv {
+    fn handle(&mut self, _: &str, request: KvRequest) -> KvResponse {
+        let key = key_of(&request);
+        let (response, update) = apply(self.data.get(&key), request);
+        if let Some(value) = update {
+            self.data.insert(key, value);
+        }
+
+        response
+    }
+}
+
+// Writes are totally ordered, but each client only promises to observe a
+// version at least as new as the last one it saw: reads may come from any
+// version since then, so a client that never writes can fall behind.
+#[derive(Debug, Default)]
+pub struct SeqKv {
+    versions: HashMap<String, Vec<(u64, Value)>>,
+    latest: u64,
+    seen: HashMap<String, u64>,
+}
+
+impl SeqKv {
+    fn value_at(&self, key: &str, version: u64) -> Option<&Value> {
+        let versions = self.versions.get(key)?;
+        let index = versions.partition_point(|&(v, _)| v <= version);
+        versions[..index].last().map(|(_, value)| value)
+    }
+}
+
+impl KvStore for SeqKv {
+    fn handle(&mut self, client: &str, request: KvRequest) -> KvResponse {
+        let key = key_of(&request);
+        let seen = self.seen.get(client).copied().unwrap_or_default();
+        if let KvRequest::Read { .. } = request {
+            let oldest = seen.max(self.latest.saturating_sub(SEQ_STALENESS));
+            let version = rand::thread_rng().gen_range(oldest..=self.latest);
+            self.seen.insert(client.to_string(), version);
+            return apply(self.value_at(&key, version), request).0;
+        }
+
+        let (response, update) = apply(self.value_at(&key, self.latest), request);
+        if let Some(value) = update {
+            self.latest += 1;
+            self.versions
+                .entry(key)
+                .or_default()
+                .push((self.latest, value));
+        }
+
+        self.seen.insert(client.to_string(), self.latest);
+        response
+    }
+}
+
+type Stamp = (i128, usize);
+
+struct Replication {
+    at: Instant,
+    replica: usize,
+    key: String,
+    stamp: Stamp,
+    value: Value,
+}
+
+// Several replicas with skewed clocks. Each request lands on a random replica
+// and writes reach the others after a delay; when two writes meet, the one
+// with the later timestamp wins, even if it was not the last to happen.
+pub struct LwwKv {
+    started: Instant,
+    replicas: Vec<HashMap<String, (Stamp, Value)>>,
+    skews: Vec<i128>,
+    pending: Vec<Replication>,
+}
+
+impl LwwKv {
+    pub fn new(replicas: usize) -> Self {
+        let mut rng = rand::thread_rng();
+        let max_skew = LWW_MAX_SKEW.as_nanos() as i128;
+        Self {
+            started: Instant::now(),
+            replicas: vec![HashMap::new(); replicas.max(1)],
+            skews: (0..replicas.max(1))
+                .map(|_| rng.gen_range(-max_skew..=max_skew))
+                .collect(),
+            pending: vec![],
+        }
+    }
+
+    fn merge(&mut self, replica: usize, key: String, stamp: Stamp, value: Value) {
+        let entry = self.replicas[replica]
+            .entry(key)
+            .or_insert_with(|| (stamp, value.clone()));
+        if stamp > entry.0 {
+            *entry = (stamp, value);
+        }
+    }
+
+    fn replicate(&mut self) {
+        let now = Instant::now();
+        let (due, pending) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition::<Vec<_>, _>(|replication| replication.at <= now);
+        self.pending = pending;
+        for replication in due {
+            self.merge(
+                replication.replica,
+                replication.key,
+                replication.stamp,
+                replication.value,
+            );
+        }
+    }
+}
+
+impl KvStore for LwwKv {
+    fn handle(&mut self, _: &str, request: KvRequest) -> KvResponse {
+        self.replicate();
+
+        let mut rng = rand::thread_rng();
+        let replica = rng.gen_range(0..self.replicas.len());
+        let key = key_of(&request);
+        let current = self.replicas[replica].get(&key).map(|(_, value)| value);
+        let (response, update) = apply(current, request);
+        let Some(value) = update else {
+            return response;
+        };
+
+        let clock = self.started.elapsed().as_nanos() as i128 + self.skews[replica];
+        let stamp = (clock, replica);
+        self.merge(replica, key.clone(), stamp, value.clone());
+        for other in (0..self.replicas.len()).filter(|&other| other != replica) {
+            let delay = rng.gen_range(Duration::ZERO..=LWW_MAX_REPLICATION_DELAY);
+            self.pending.push(Replication {
+                at: Instant::now() + delay,
+                replica: other,
+                key: key.clone(),
+                stamp,
+                value: value.clone(),
+            });
+        }
+
+        response
+    }
+}
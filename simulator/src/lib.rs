@@ -0,0 +1,5 @@
+pub mod error;
+pub mod kv;
+pub mod network;
+pub mod simulation;
+pub mod workload;
@@ -0,0 +1,285 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use checker::history::{is_server, LoggedMessage, MessageLog};
+use maelstrom::{
+    error::MALFORMED_REQUEST,
+    kv::{KvKind, KvRequest, KvResponse},
+    message::Message,
+};
+use serde_json::Value;
+
+use crate::{
+    error::SimulatorError,
+    kv::{self, KvStore},
+};
+
+enum NodeEvent {
+    Line(String),
+    Exited(usize),
+}
+
+struct NodeProcess {
+    id: String,
+    child: Child,
+    stdin: ChildStdin,
+}
+
+// Routes messages between node processes, clients and in-process services.
+// Every message is logged when sent and delivered after a fixed latency,
+// unless a partition separates two server nodes.
+pub struct Network {
+    started: Instant,
+    latency: Duration,
+    nodes: Vec<NodeProcess>,
+    readers: Vec<JoinHandle<()>>,
+    indices: HashMap<String, usize>,
+    stores: HashMap<String, Box<dyn KvStore>>,
+    next_store_message_id: usize,
+    queue: BinaryHeap<Reverse<(Instant, u64)>>,
+    in_flight: HashMap<u64, Message<Value>>,
+    next_sequence: u64,
+    components: Option<Vec<usize>>,
+    log: MessageLog,
+    events: Receiver<NodeEvent>,
+}
+
+impl Network {
+    pub fn spawn(
+        binary: &Path,
+        node_count: usize,
+        latency: Duration,
+        env: &[(String, String)],
+        logs: Option<&Path>,
+    ) -> Result<Self, SimulatorError> {
+        let (tx, events) = mpsc::channel();
+        let mut nodes = vec![];
+        let mut readers = vec![];
+        for index in 0..node_count {
+            let id = format!("n{index}");
+            let stderr = match logs {
+                Some(logs) => File::create(logs.join(format!("{id}.log")))
+                    .map(Stdio::from)
+                    .map_err(|_| SimulatorError::IOError)?,
+                None => Stdio::null(),
+            };
+
+            let mut child = Command::new(binary)
+                .envs(env.iter().map(|(key, value)| (key, value)))
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(stderr)
+                .spawn()
+                .map_err(|_| SimulatorError::SpawnError(binary.display().to_string()))?;
+
+            let stdin = child.stdin.take().ok_or(SimulatorError::IOError)?;
+            let stdout = child.stdout.take().ok_or(SimulatorError::IOError)?;
+            let tx = tx.clone();
+            readers.push(std::thread::spawn(move || {
+                for line in BufReader::new(stdout).lines() {
+                    let Ok(line) = line else {
+                        break;
+                    };
+
+                    if tx.send(NodeEvent::Line(line)).is_err() {
+                        return;
+                    }
+                }
+
+                let _ = tx.send(NodeEvent::Exited(index));
+            }));
+
+            nodes.push(NodeProcess { id, child, stdin });
+        }
+
+        let indices = nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (node.id.clone(), index))
+            .collect();
+
+        Ok(Self {
+            started: Instant::now(),
+            latency,
+            nodes,
+            readers,
+            indices,
+            stores: KvKind::ALL
+                .into_iter()
+                .map(|kind| (kind.id().to_string(), kv::store(kind)))
+                .collect(),
+            next_store_message_id: 1,
+            queue: BinaryHeap::new(),
+            in_flight: HashMap::new(),
+            next_sequence: 0,
+            components: None,
+            log: MessageLog::default(),
+            events,
+        })
+    }
+
+    pub fn node_ids(&self) -> Vec<String> {
+        self.nodes.iter().map(|node| node.id.clone()).collect()
+    }
+
+    pub fn elapsed(&self) -> u64 {
+        self.started.elapsed().as_nanos() as u64
+    }
+
+    pub fn partition(&mut self, components: Vec<usize>) {
+        self.components = Some(components);
+    }
+
+    pub fn heal(&mut self) {
+        self.components = None;
+    }
+
+    pub fn is_partitioned(&self) -> bool {
+        self.components.is_some()
+    }
+
+    fn is_cut(&self, src: &str, dest: &str) -> bool {
+        let Some(components) = &self.components else {
+            return false;
+        };
+
+        match (self.indices.get(src), self.indices.get(dest)) {
+            (Some(&src), Some(&dest)) => components[src] != components[dest],
+            _ => false,
+        }
+    }
+
+    pub fn send(&mut self, message: Message<Value>) {
+        self.log.push(LoggedMessage {
+            time: self.elapsed(),
+            message: message.clone(),
+        });
+
+        if is_server(message.src())
+            && is_server(message.dest())
+            && self.is_cut(message.src(), message.dest())
+        {
+            return;
+        }
+
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.queue
+            .push(Reverse((Instant::now() + self.latency, sequence)));
+        self.in_flight.insert(sequence, message);
+    }
+
+    // Key-value requests are answered in-process, with the reply sent back over
+    // the network like any other message.
+    fn serve(&mut self, message: &Message<Value>) -> bool {
+        let Some(store) = self.stores.get_mut(message.dest()) else {
+            return false;
+        };
+
+        let response = match serde_json::from_value::<KvRequest>(message.payload().clone()) {
+            Ok(request) => store.handle(message.src(), request),
+            Err(_) => KvResponse::error(MALFORMED_REQUEST, "malformed key-value request"),
+        };
+
+        let message_id = self.next_store_message_id;
+        self.next_store_message_id += 1;
+        self.send(Message::new(
+            message.dest().to_string(),
+            message.src().to_string(),
+            Some(message_id),
+            message.message_id(),
+            serde_json::to_value(response).expect("kv responses serialize"),
+        ));
+
+        true
+    }
+
+    fn deliver(&mut self, message: &Message<Value>) -> Result<bool, SimulatorError> {
+        if self.serve(message) {
+            return Ok(true);
+        }
+
+        let Some(&index) = self.indices.get(message.dest()) else {
+            return Ok(false);
+        };
+
+        let node = &mut self.nodes[index];
+        message
+            .write_to(&mut node.stdin)
+            .map_err(|_| SimulatorError::NodeExited(node.id.clone()))?;
+
+        Ok(true)
+    }
+
+    // Runs the network until `deadline`, returning early with any messages
+    // that arrived for destinations other than node processes.
+    pub fn poll(&mut self, deadline: Instant) -> Result<Vec<Message<Value>>, SimulatorError> {
+        let mut arrived = vec![];
+        loop {
+            let now = Instant::now();
+            while let Some(&Reverse((at, sequence))) = self.queue.peek() {
+                if at > now {
+                    break;
+                }
+
+                self.queue.pop();
+                let message = self
+                    .in_flight
+                    .remove(&sequence)
+                    .expect("queued message is in flight");
+
+                if !self.deliver(&message)? {
+                    arrived.push(message);
+                }
+            }
+
+            if !arrived.is_empty() || now >= deadline {
+                return Ok(arrived);
+            }
+
+            let wake = match self.queue.peek() {
+                Some(&Reverse((at, _))) => at.min(deadline),
+                None => deadline,
+            };
+
+            match self
+                .events
+                .recv_timeout(wake.saturating_duration_since(now))
+            {
+                Ok(NodeEvent::Line(line)) => {
+                    let message = line
+                        .parse::<Message<Value>>()
+                        .map_err(|_| SimulatorError::MessageParseError(line))?;
+                    self.send(message);
+                }
+                Ok(NodeEvent::Exited(index)) => {
+                    return Err(SimulatorError::NodeExited(self.nodes[index].id.clone()));
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return Err(SimulatorError::IOError),
+            }
+        }
+    }
+
+    pub fn shutdown(mut self) -> MessageLog {
+        for node in &mut self.nodes {
+            let _ = node.child.kill();
+            let _ = node.child.wait();
+        }
+
+        for reader in self.readers.drain(..) {
+            let _ = reader.join();
+        }
+
+        self.log
+    }
+}
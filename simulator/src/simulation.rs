@@ -0,0 +1,391 @@
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use checker::history::{Event, EventKind, History, MessageLog};
+use maelstrom::message::Message;
+use rand::prelude::*;
+use serde_json::{json, Value};
+
+use crate::{
+    error::SimulatorError,
+    network::Network,
+    workload::{Check, Request, Workload},
+};
+
+const INIT_TIMEOUT: Duration = Duration::from_secs(10);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub binary: PathBuf,
+    pub node_count: usize,
+    pub concurrency: usize,
+    pub time_limit: Duration,
+    pub rate: f64,
+    pub latency: Duration,
+    pub partitions: bool,
+    pub nemesis_interval: Duration,
+    pub store: Option<PathBuf>,
+    pub env: Vec<(String, String)>,
+}
+
+impl Config {
+    pub fn new(binary: PathBuf, node_count: usize) -> Self {
+        Self {
+            binary,
+            node_count,
+            concurrency: node_count,
+            time_limit: Duration::from_secs(20),
+            rate: 10.0,
+            latency: Duration::ZERO,
+            partitions: false,
+            nemesis_interval: Duration::from_secs(10),
+            store: None,
+            env: vec![],
+        }
+    }
+}
+
+pub struct Report {
+    pub history: History,
+    pub messages: MessageLog,
+    pub check: Check,
+}
+
+struct Pending {
+    request: Request,
+    message_id: usize,
+    deadline: Instant,
+}
+
+struct Client {
+    id: String,
+    node: usize,
+    process: usize,
+    pending: Option<Pending>,
+}
+
+struct Clients {
+    node_ids: Vec<String>,
+    clients: Vec<Client>,
+    next_process: usize,
+    next_message_id: usize,
+    history: History,
+}
+
+impl Clients {
+    fn new(node_ids: Vec<String>) -> Self {
+        Self {
+            node_ids,
+            clients: vec![],
+            next_process: 0,
+            next_message_id: 1,
+            history: History::default(),
+        }
+    }
+
+    fn add(&mut self, node: usize) -> usize {
+        self.clients.push(Client {
+            id: format!("c{}", self.clients.len() + 1),
+            node,
+            process: self.next_process,
+            pending: None,
+        });
+
+        self.next_process += 1;
+        self.clients.len() - 1
+    }
+
+    fn message_id(&mut self) -> usize {
+        let message_id = self.next_message_id;
+        self.next_message_id += 1;
+        message_id
+    }
+
+    fn is_idle(&self) -> bool {
+        self.clients.iter().all(|client| client.pending.is_none())
+    }
+
+    fn idle(&self) -> Vec<usize> {
+        (0..self.clients.len())
+            .filter(|&index| self.clients[index].pending.is_none())
+            .collect()
+    }
+
+    fn record(&mut self, client: usize, kind: EventKind, f: &str, value: Value, time: u64) {
+        let client = &self.clients[client];
+        self.history.push(Event {
+            process: client.process,
+            kind,
+            f: f.to_string(),
+            value,
+            time,
+            node: Some(self.node_ids[client.node].clone()),
+        });
+    }
+
+    fn invoke(&mut self, network: &mut Network, client: usize, request: Request) {
+        let message_id = self.message_id();
+        let (id, node) = (&self.clients[client].id, self.clients[client].node);
+        network.send(Message::new(
+            id.clone(),
+            self.node_ids[node].clone(),
+            Some(message_id),
+            None,
+            request.body.clone(),
+        ));
+
+        let time = network.elapsed();
+        self.record(
+            client,
+            EventKind::Invoke,
+            &request.f,
+            request.value.clone(),
+            time,
+        );
+        self.clients[client].pending = Some(Pending {
+            request,
+            message_id,
+            deadline: Instant::now() + REQUEST_TIMEOUT,
+        });
+    }
+
+    fn complete(&mut self, network: &Network, workload: &mut dyn Workload, reply: Message<Value>) {
+        let Some(client) = self.clients.iter().position(|client| {
+            client.id == reply.dest()
+                && client
+                    .pending
+                    .as_ref()
+                    .is_some_and(|pending| Some(pending.message_id) == reply.in_reply_to())
+        }) else {
+            return;
+        };
+
+        let pending = self.clients[client]
+            .pending
+            .take()
+            .expect("client is pending");
+        let body = reply.payload();
+        let (kind, value) = if body["type"] == "error" {
+            (error_kind(&body["code"]), pending.request.value.clone())
+        } else {
+            workload.complete(&pending.request, body)
+        };
+
+        self.record(client, kind, &pending.request.f, value, network.elapsed());
+    }
+
+    // Operations without a reply may still have happened: they are recorded as
+    // indeterminate and the process is retired, as in Jepsen.
+    fn expire(&mut self, network: &Network) {
+        let now = Instant::now();
+        for client in 0..self.clients.len() {
+            let expired = self.clients[client]
+                .pending
+                .as_ref()
+                .is_some_and(|pending| pending.deadline <= now);
+
+            if expired {
+                let pending = self.clients[client]
+                    .pending
+                    .take()
+                    .expect("client is pending");
+                let time = network.elapsed();
+                self.record(
+                    client,
+                    EventKind::Info,
+                    &pending.request.f,
+                    Value::Null,
+                    time,
+                );
+                self.clients[client].process = self.next_process;
+                self.next_process += 1;
+            }
+        }
+    }
+}
+
+// Maelstrom error codes that guarantee the operation did not take effect.
+fn error_kind(code: &Value) -> EventKind {
+    match code.as_u64() {
+        Some(1 | 10..=12 | 20..=22 | 30) => EventKind::Fail,
+        _ => EventKind::Info,
+    }
+}
+
+fn initialize(network: &mut Network, node_ids: &[String]) -> Result<(), SimulatorError> {
+    for (index, id) in node_ids.iter().enumerate() {
+        network.send(Message::new(
+            String::from("c0"),
+            id.clone(),
+            Some(index + 1),
+            None,
+            json!({ "type": "init", "node_id": id, "node_ids": node_ids }),
+        ));
+    }
+
+    let deadline = Instant::now() + INIT_TIMEOUT;
+    let mut acknowledged = vec![false; node_ids.len()];
+    while acknowledged.contains(&false) {
+        if Instant::now() >= deadline {
+            let missing = acknowledged.iter().position(|&ok| !ok).unwrap_or_default();
+            return Err(SimulatorError::InitTimeout(node_ids[missing].clone()));
+        }
+
+        for reply in network.poll(deadline)? {
+            if let Some(index) = reply.in_reply_to().filter(|&id| id <= node_ids.len()) {
+                acknowledged[index - 1] |= reply.payload()["type"] == "init_ok";
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn random_halves(node_count: usize) -> Vec<usize> {
+    let mut nodes = (0..node_count).collect::<Vec<_>>();
+    nodes.shuffle(&mut rand::thread_rng());
+
+    let mut components = vec![0; node_count];
+    for &node in &nodes[..node_count / 2] {
+        components[node] = 1;
+    }
+
+    components
+}
+
+fn save(store: &Path, history: &History, messages: &MessageLog) -> Result<(), SimulatorError> {
+    let create = |name: &str| {
+        File::create(store.join(name))
+            .map(BufWriter::new)
+            .map_err(|_| SimulatorError::IOError)
+    };
+
+    history
+        .write_to(&mut create("history.jsonl")?)
+        .map_err(|_| SimulatorError::IOError)?;
+    messages
+        .write_to(&mut create("messages.jsonl")?)
+        .map_err(|_| SimulatorError::IOError)
+}
+
+pub fn run(config: &Config, workload: &mut dyn Workload) -> Result<Report, SimulatorError> {
+    let logs = config.store.as_ref().map(|store| store.join("node-logs"));
+    if let Some(logs) = &logs {
+        std::fs::create_dir_all(logs).map_err(|_| SimulatorError::IOError)?;
+    }
+
+    let mut network = Network::spawn(
+        &config.binary,
+        config.node_count,
+        config.latency,
+        &config.env,
+        logs.as_deref(),
+    )?;
+
+    let result = drive(config, workload, &mut network);
+    let messages = network.shutdown();
+    let history = result?;
+
+    if let Some(store) = &config.store {
+        save(store, &history, &messages)?;
+    }
+
+    let check = workload.check(&history, &messages);
+    Ok(Report {
+        history,
+        messages,
+        check,
+    })
+}
+
+fn drive(
+    config: &Config,
+    workload: &mut dyn Workload,
+    network: &mut Network,
+) -> Result<History, SimulatorError> {
+    let node_ids = network.node_ids();
+    initialize(network, &node_ids)?;
+
+    let mut clients = Clients::new(node_ids.clone());
+    for (node, body) in workload.setup(&node_ids) {
+        let message_id = clients.message_id();
+        network.send(Message::new(
+            String::from("c0"),
+            node_ids[node].clone(),
+            Some(message_id),
+            None,
+            body,
+        ));
+    }
+
+    for index in 0..config.concurrency.max(1) {
+        clients.add(index % node_ids.len());
+    }
+
+    let mut rng = rand::thread_rng();
+    let interval = Duration::from_secs_f64(1.0 / config.rate.max(0.001));
+    let started = Instant::now();
+    let end = started + config.time_limit;
+    let mut next_request = started;
+    let mut next_nemesis = started + config.nemesis_interval;
+    while Instant::now() < end {
+        let now = Instant::now();
+        if now >= next_request {
+            if let Some(&client) = clients.idle().choose(&mut rng) {
+                clients.invoke(network, client, workload.generate());
+            }
+
+            next_request = next_request.max(now - interval) + interval;
+        }
+
+        if config.partitions && now >= next_nemesis {
+            if network.is_partitioned() {
+                network.heal();
+            } else {
+                network.partition(random_halves(node_ids.len()));
+            }
+
+            next_nemesis += config.nemesis_interval;
+        }
+
+        clients.expire(network);
+        let mut deadline = next_request.min(end);
+        if config.partitions {
+            deadline = deadline.min(next_nemesis);
+        }
+
+        for reply in network.poll(deadline)? {
+            clients.complete(network, workload, reply);
+        }
+    }
+
+    network.heal();
+    let recovered = Instant::now() + workload.recovery();
+    while !clients.is_idle() || Instant::now() < recovered {
+        clients.expire(network);
+        for reply in network.poll(Instant::now() + Duration::from_millis(10))? {
+            clients.complete(network, workload, reply);
+        }
+    }
+
+    for node in 0..node_ids.len() {
+        if let Some(request) = workload.final_request(node) {
+            let client = clients.add(node);
+            clients.invoke(network, client, request);
+        }
+    }
+
+    while !clients.is_idle() {
+        clients.expire(network);
+        for reply in network.poll(Instant::now() + Duration::from_millis(10))? {
+            clients.complete(network, workload, reply);
+        }
+    }
+
+    Ok(clients.history)
+}
@@ -0,0 +1,502 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use checker::{
+    broadcast::{self, Thresholds},
+    counter,
+    history::{EventKind, History, MessageLog},
+    kafka,
+    txn::{self, ConsistencyModel},
+};
+use rand::prelude::*;
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub f: String,
+    pub value: Value,
+    pub body: Value,
+}
+
+impl Request {
+    pub fn new(f: &str, value: Value, body: Value) -> Self {
+        Self {
+            f: f.to_string(),
+            value,
+            body,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Check {
+    pub valid: bool,
+    pub summary: String,
+    pub problems: Vec<String>,
+}
+
+impl Check {
+    pub fn from_problems(summary: String, problems: Vec<String>) -> Self {
+        Self {
+            valid: problems.is_empty(),
+            summary,
+            problems,
+        }
+    }
+}
+
+pub trait Workload {
+    fn name(&self) -> &str;
+
+    // Messages sent to nodes before any client operation, as (node, body).
+    fn setup(&mut self, _node_ids: &[String]) -> Vec<(usize, Value)> {
+        vec![]
+    }
+
+    fn generate(&mut self) -> Request;
+
+    fn complete(&mut self, request: &Request, reply: &Value) -> (EventKind, Value);
+
+    // Time given to the cluster to converge, with partitions healed, before
+    // the final requests are issued.
+    fn recovery(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    fn final_request(&mut self, _node: usize) -> Option<Request> {
+        None
+    }
+
+    fn check(&self, history: &History, messages: &MessageLog) -> Check;
+}
+
+#[derive(Debug, Default)]
+pub struct EchoWorkload {
+    next: usize,
+}
+
+impl Workload for EchoWorkload {
+    fn name(&self) -> &str {
+        "echo"
+    }
+
+    fn generate(&mut self) -> Request {
+        self.next += 1;
+        let echo = format!("Please echo {}", self.next);
+        Request::new("echo", json!(echo), json!({ "type": "echo", "echo": echo }))
+    }
+
+    fn complete(&mut self, _: &Request, reply: &Value) -> (EventKind, Value) {
+        (EventKind::Ok, reply["echo"].clone())
+    }
+
+    fn check(&self, history: &History, _: &MessageLog) -> Check {
+        let operations = history.operations();
+        let problems = operations
+            .iter()
+            .filter(|operation| operation.is_ok())
+            .filter(|operation| operation.output.as_ref() != Some(&operation.input))
+            .map(|operation| {
+                format!(
+                    "echo {} came back as {:?}",
+                    operation.input, operation.output
+                )
+            })
+            .collect();
+
+        Check::from_problems(format!("{} echoes", operations.len()), problems)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct UniqueIdsWorkload;
+
+impl Workload for UniqueIdsWorkload {
+    fn name(&self) -> &str {
+        "unique-ids"
+    }
+
+    fn generate(&mut self) -> Request {
+        Request::new("generate", Value::Null, json!({ "type": "generate" }))
+    }
+
+    fn complete(&mut self, _: &Request, reply: &Value) -> (EventKind, Value) {
+        (EventKind::Ok, reply["id"].clone())
+    }
+
+    fn check(&self, history: &History, _: &MessageLog) -> Check {
+        let mut seen = HashSet::new();
+        let mut problems = vec![];
+        let mut generated = 0;
+        for operation in history.operations() {
+            let Some(id) = operation
+                .output
+                .filter(|_| operation.outcome == EventKind::Ok)
+            else {
+                continue;
+            };
+
+            generated += 1;
+            let id = id.to_string();
+            if !seen.insert(id.clone()) {
+                problems.push(format!("duplicate id {id}"));
+            }
+        }
+
+        Check::from_problems(format!("{generated} ids generated"), problems)
+    }
+}
+
+pub struct BroadcastWorkload {
+    thresholds: Thresholds,
+    next: usize,
+    rng: ThreadRng,
+}
+
+impl BroadcastWorkload {
+    pub fn new(thresholds: Thresholds) -> Self {
+        Self {
+            thresholds,
+            next: 0,
+            rng: rand::thread_rng(),
+        }
+    }
+}
+
+// Maelstrom's default broadcast topology: nodes laid out row by row on a
+// square grid, each connected to its horizontal and vertical neighbours.
+pub fn grid_topology(node_ids: &[String]) -> Value {
+    let side = (node_ids.len() as f64).sqrt().ceil().max(1.0) as usize;
+    let topology = node_ids
+        .iter()
+        .enumerate()
+        .map(|(index, id)| {
+            let (row, column) = (index / side, index % side);
+            let mut neighbors = vec![];
+            if row > 0 {
+                neighbors.push(index - side);
+            }
+            if column > 0 {
+                neighbors.push(index - 1);
+            }
+            if column + 1 < side && index + 1 < node_ids.len() {
+                neighbors.push(index + 1);
+            }
+            if index + side < node_ids.len() {
+                neighbors.push(index + side);
+            }
+
+            let neighbors = neighbors
+                .into_iter()
+                .map(|neighbor| node_ids[neighbor].clone())
+                .collect::<Vec<_>>();
+            (id.clone(), json!(neighbors))
+        })
+        .collect::<serde_json::Map<_, _>>();
+
+    Value::Object(topology)
+}
+
+impl Workload for BroadcastWorkload {
+    fn name(&self) -> &str {
+        "broadcast"
+    }
+
+    fn setup(&mut self, node_ids: &[String]) -> Vec<(usize, Value)> {
+        let topology = grid_topology(node_ids);
+        (0..node_ids.len())
+            .map(|node| (node, json!({ "type": "topology", "topology": topology })))
+            .collect()
+    }
+
+    fn generate(&mut self) -> Request {
+        if self.rng.gen_bool(0.5) {
+            let value = self.next;
+            self.next += 1;
+            Request::new(
+                "broadcast",
+                json!(value),
+                json!({ "type": "broadcast", "message": value }),
+            )
+        } else {
+            Request::new("read", Value::Null, json!({ "type": "read" }))
+        }
+    }
+
+    fn complete(&mut self, request: &Request, reply: &Value) -> (EventKind, Value) {
+        match request.f.as_str() {
+            "read" => (EventKind::Ok, reply["messages"].clone()),
+            _ => (EventKind::Ok, request.value.clone()),
+        }
+    }
+
+    fn recovery(&self) -> Duration {
+        Duration::from_secs(3)
+    }
+
+    fn final_request(&mut self, _: usize) -> Option<Request> {
+        Some(Request::new("read", Value::Null, json!({ "type": "read" })))
+    }
+
+    fn check(&self, history: &History, messages: &MessageLog) -> Check {
+        let report = broadcast::check(history, messages);
+        Check::from_problems(report.to_string(), report.violations(&self.thresholds))
+    }
+}
+
+pub struct CounterWorkload {
+    thresholds: Thresholds,
+    decrements: bool,
+    rng: ThreadRng,
+}
+
+impl CounterWorkload {
+    pub fn grow_only(thresholds: Thresholds) -> Self {
+        Self {
+            thresholds,
+            decrements: false,
+            rng: rand::thread_rng(),
+        }
+    }
+
+    pub fn pn(thresholds: Thresholds) -> Self {
+        Self {
+            thresholds,
+            decrements: true,
+            rng: rand::thread_rng(),
+        }
+    }
+}
+
+impl Workload for CounterWorkload {
+    fn name(&self) -> &str {
+        if self.decrements {
+            "pn-counter"
+        } else {
+            "g-counter"
+        }
+    }
+
+    fn generate(&mut self) -> Request {
+        if self.rng.gen_bool(0.5) {
+            let delta = match self.decrements {
+                true => self.rng.gen_range(-5..5),
+                false => self.rng.gen_range(0..5),
+            };
+            Request::new(
+                "add",
+                json!(delta),
+                json!({ "type": "add", "delta": delta }),
+            )
+        } else {
+            Request::new("read", Value::Null, json!({ "type": "read" }))
+        }
+    }
+
+    fn complete(&mut self, request: &Request, reply: &Value) -> (EventKind, Value) {
+        match request.f.as_str() {
+            "read" => (EventKind::Ok, reply["value"].clone()),
+            _ => (EventKind::Ok, request.value.clone()),
+        }
+    }
+
+    fn recovery(&self) -> Duration {
+        Duration::from_secs(3)
+    }
+
+    fn final_request(&mut self, _: usize) -> Option<Request> {
+        Some(Request::new("read", Value::Null, json!({ "type": "read" })))
+    }
+
+    fn check(&self, history: &History, messages: &MessageLog) -> Check {
+        let report = counter::check(history, messages);
+        Check::from_problems(report.to_string(), report.violations(&self.thresholds))
+    }
+}
+
+pub struct KafkaWorkload {
+    keys: Vec<String>,
+    next_msg: usize,
+    consumed: HashMap<String, u64>,
+    rng: ThreadRng,
+}
+
+impl KafkaWorkload {
+    pub fn new(key_count: usize) -> Self {
+        Self {
+            keys: (0..key_count.max(1)).map(|key| key.to_string()).collect(),
+            next_msg: 0,
+            consumed: HashMap::new(),
+            rng: rand::thread_rng(),
+        }
+    }
+
+    fn offsets(&self) -> Value {
+        let offsets = self
+            .keys
+            .iter()
+            .map(|key| (key.clone(), json!(self.consumed.get(key).unwrap_or(&0))))
+            .collect::<serde_json::Map<_, _>>();
+
+        Value::Object(offsets)
+    }
+}
+
+impl Default for KafkaWorkload {
+    fn default() -> Self {
+        Self::new(5)
+    }
+}
+
+impl Workload for KafkaWorkload {
+    fn name(&self) -> &str {
+        "kafka"
+    }
+
+    fn generate(&mut self) -> Request {
+        match self.rng.gen_range(0..10) {
+            0..=4 => {
+                let key = self.keys.choose(&mut self.rng).expect("keys").clone();
+                let msg = self.next_msg;
+                self.next_msg += 1;
+                Request::new(
+                    "send",
+                    json!([key, msg]),
+                    json!({ "type": "send", "key": key, "msg": msg }),
+                )
+            }
+            5..=7 => {
+                let offsets = self.offsets();
+                Request::new(
+                    "poll",
+                    offsets.clone(),
+                    json!({ "type": "poll", "offsets": offsets }),
+                )
+            }
+            8 => {
+                let offsets = self.offsets();
+                Request::new(
+                    "commit_offsets",
+                    offsets.clone(),
+                    json!({ "type": "commit_offsets", "offsets": offsets }),
+                )
+            }
+            _ => Request::new(
+                "list_committed_offsets",
+                json!(self.keys),
+                json!({ "type": "list_committed_offsets", "keys": self.keys }),
+            ),
+        }
+    }
+
+    fn complete(&mut self, request: &Request, reply: &Value) -> (EventKind, Value) {
+        match request.f.as_str() {
+            "send" => {
+                let mut value = request.value.clone();
+                if let Some(value) = value.as_array_mut() {
+                    value.push(reply["offset"].clone());
+                }
+
+                (EventKind::Ok, value)
+            }
+            "poll" => {
+                let msgs = reply["msgs"].as_object().into_iter().flatten();
+                for (key, msgs) in msgs {
+                    let last = msgs
+                        .as_array()
+                        .and_then(|msgs| msgs.last())
+                        .and_then(|msg| msg[0].as_u64());
+                    if let Some(last) = last {
+                        let consumed = self.consumed.entry(key.clone()).or_default();
+                        *consumed = (*consumed).max(last + 1);
+                    }
+                }
+
+                (EventKind::Ok, reply["msgs"].clone())
+            }
+            "list_committed_offsets" => (EventKind::Ok, reply["offsets"].clone()),
+            _ => (EventKind::Ok, request.value.clone()),
+        }
+    }
+
+    fn recovery(&self) -> Duration {
+        Duration::from_secs(3)
+    }
+
+    fn check(&self, history: &History, _: &MessageLog) -> Check {
+        let report = kafka::check(history);
+        Check::from_problems(report.to_string(), report.problems)
+    }
+}
+
+pub struct TxnWorkload {
+    key_count: usize,
+    max_length: usize,
+    model: ConsistencyModel,
+    next_value: usize,
+    rng: ThreadRng,
+}
+
+impl TxnWorkload {
+    pub fn new(key_count: usize, max_length: usize) -> Self {
+        Self {
+            key_count: key_count.max(1),
+            max_length: max_length.max(1),
+            model: ConsistencyModel::ReadCommitted,
+            next_value: 1,
+            rng: rand::thread_rng(),
+        }
+    }
+
+    pub fn with_model(mut self, model: ConsistencyModel) -> Self {
+        self.model = model;
+        self
+    }
+}
+
+impl Default for TxnWorkload {
+    fn default() -> Self {
+        Self::new(10, 4)
+    }
+}
+
+impl Workload for TxnWorkload {
+    fn name(&self) -> &str {
+        "txn-rw-register"
+    }
+
+    // Every write puts a fresh value, so reads can be traced back to the
+    // transaction that wrote them.
+    fn generate(&mut self) -> Request {
+        let length = self.rng.gen_range(1..=self.max_length);
+        let txn = (0..length)
+            .map(|_| {
+                let key = self.rng.gen_range(0..self.key_count);
+                if self.rng.gen_bool(0.5) {
+                    json!(["r", key, null])
+                } else {
+                    let value = self.next_value;
+                    self.next_value += 1;
+                    json!(["w", key, value])
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Request::new("txn", json!(txn), json!({ "type": "txn", "txn": txn }))
+    }
+
+    fn complete(&mut self, _: &Request, reply: &Value) -> (EventKind, Value) {
+        (EventKind::Ok, reply["txn"].clone())
+    }
+
+    fn recovery(&self) -> Duration {
+        Duration::from_secs(3)
+    }
+
+    fn check(&self, history: &History, _: &MessageLog) -> Check {
+        let report = txn::check(history);
+        Check::from_problems(report.to_string(), report.violations(self.model))
+    }
+}
@@ -0,0 +1,290 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use maelstrom::{
+    config::{NodeConfig, Settings},
+    error::MaelstromError,
+    message::{InitializationRequest, Message},
+    node::MaelstromNode,
+    service::Service,
+    txn::MicroOp,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum TxnRequest {
+    Txn { txn: Vec<MicroOp> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum TxnResponse {
+    TxnOk { txn: Vec<MicroOp> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum PeerPayload {
+    Replicate { write_sets: Vec<WriteSet> },
+    ReplicateOk { versions: Vec<Version> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Isolation {
+    ReadUncommitted,
+    ReadCommitted,
+}
+
+impl Isolation {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "read-uncommitted" => Some(Self::ReadUncommitted),
+            "read-committed" => Some(Self::ReadCommitted),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TxnConfig {
+    isolation: Isolation,
+}
+
+impl NodeConfig for TxnConfig {
+    const PREFIX: &'static str = "TXN";
+
+    fn load(settings: &mut Settings) -> Result<Self, MaelstromError> {
+        Ok(Self {
+            isolation: settings.parse(
+                "isolation",
+                Isolation::ReadCommitted,
+                "read-uncommitted or read-committed",
+                Isolation::parse,
+            )?,
+        })
+    }
+}
+
+// Versions order transactions by a Lamport clock, ties broken by node id, so
+// every replica keeps the same value for a key no matter the order write sets
+// arrive in.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+struct Version {
+    clock: u64,
+    node: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct WriteSet {
+    version: Version,
+    writes: Vec<(usize, usize)>,
+}
+
+// Transactions run against the local replica and are answered at once, so the
+// node stays available under partitions. All writes of a transaction share
+// one version, newer than anything the replica has seen, which orders
+// conflicting writers the same way on every key and every replica (no G0).
+//
+// Under read-uncommitted, writes land in the store as they execute and write
+// sets carry every write in order. Under read-committed, writes are buffered
+// and installed only once the transaction commits, and write sets carry just
+// the final value of each key, so no replica ever exposes a write set in part
+// or an intermediate value (no G1a or G1b). Write sets are resent to each peer
+// until it acknowledges them.
+struct TxnNode {
+    id: String,
+    network: Vec<String>,
+    isolation: Isolation,
+    clock: u64,
+    store: HashMap<usize, (Version, usize)>,
+    write_sets: BTreeMap<Version, WriteSet>,
+    acknowledged: HashMap<String, HashSet<Version>>,
+}
+
+impl TxnNode {
+    fn install(&mut self, write_set: &WriteSet) {
+        self.clock = self.clock.max(write_set.version.clock);
+        for &(key, value) in &write_set.writes {
+            match self.store.get(&key) {
+                Some((current, _)) if *current > write_set.version => {}
+                _ => {
+                    self.store.insert(key, (write_set.version.clone(), value));
+                }
+            }
+        }
+    }
+
+    fn execute(&mut self, txn: &[MicroOp]) -> Vec<MicroOp> {
+        self.clock += 1;
+        let mut write_set = WriteSet {
+            version: Version {
+                clock: self.clock,
+                node: self.id.clone(),
+            },
+            writes: vec![],
+        };
+
+        let mut buffered = HashMap::new();
+        let completed = txn
+            .iter()
+            .map(|op| match *op {
+                MicroOp::Read { key, .. } => MicroOp::Read {
+                    key,
+                    value: buffered
+                        .get(&key)
+                        .or_else(|| self.store.get(&key).map(|(_, value)| value))
+                        .copied(),
+                },
+                MicroOp::Write { key, value } => {
+                    match self.isolation {
+                        Isolation::ReadUncommitted => {
+                            self.store.insert(key, (write_set.version.clone(), value));
+                            write_set.writes.push((key, value));
+                        }
+                        Isolation::ReadCommitted => {
+                            buffered.insert(key, value);
+                        }
+                    }
+
+                    *op
+                }
+            })
+            .collect();
+
+        if self.isolation == Isolation::ReadCommitted {
+            write_set.writes = buffered.into_iter().collect();
+            self.install(&write_set);
+        }
+
+        if !write_set.writes.is_empty() {
+            self.write_sets.insert(write_set.version.clone(), write_set);
+        }
+
+        completed
+    }
+
+    // Forgets write sets every peer has acknowledged.
+    fn prune(&mut self) {
+        let acknowledged = &self.acknowledged;
+        self.write_sets.retain(|version, _| {
+            !acknowledged
+                .values()
+                .all(|versions| versions.contains(version))
+        });
+
+        for versions in self.acknowledged.values_mut() {
+            versions.retain(|version| self.write_sets.contains_key(version));
+        }
+    }
+}
+
+impl MaelstromNode for TxnNode {
+    type InputPayload = TxnRequest;
+    type OutputPayload = TxnResponse;
+    type PeerPayload = PeerPayload;
+    type Config = TxnConfig;
+
+    fn new(init_message: &Message<InitializationRequest>, config: Self::Config) -> Self {
+        let InitializationRequest::Init { id, neighbors } = init_message.payload();
+        let network = neighbors
+            .iter()
+            .filter(|&neighbor| neighbor != id)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let acknowledged = network
+            .iter()
+            .map(|neighbor| (neighbor.clone(), HashSet::new()))
+            .collect();
+
+        Self {
+            id: id.clone(),
+            network,
+            isolation: config.isolation,
+            clock: 0,
+            store: HashMap::new(),
+            write_sets: BTreeMap::new(),
+            acknowledged,
+        }
+    }
+
+    fn handle(
+        &mut self,
+        message: &Message<Self::InputPayload>,
+        _: &mut Service,
+    ) -> Result<Option<Self::OutputPayload>, MaelstromError>
+    where
+        Self: Sized,
+    {
+        match message.payload() {
+            TxnRequest::Txn { txn } => Ok(Some(TxnResponse::TxnOk {
+                txn: self.execute(txn),
+            })),
+        }
+    }
+
+    fn start(&mut self, service: &mut Service) -> Result<(), MaelstromError> {
+        service.every(Duration::from_millis(500));
+        Ok(())
+    }
+
+    fn handle_timer(&mut self, _: usize, service: &mut Service) -> Result<(), MaelstromError> {
+        self.prune();
+        for neighbor in &self.network {
+            let acknowledged = &self.acknowledged[neighbor];
+            let write_sets = self
+                .write_sets
+                .values()
+                .filter(|write_set| !acknowledged.contains(&write_set.version))
+                .cloned()
+                .collect::<Vec<_>>();
+
+            if !write_sets.is_empty() {
+                service.peer_rpc(
+                    self.id.clone(),
+                    neighbor.clone(),
+                    PeerPayload::Replicate { write_sets },
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_peer(
+        &mut self,
+        message: &Message<Self::PeerPayload>,
+        _: &mut Service,
+    ) -> Result<Option<Self::PeerPayload>, MaelstromError> {
+        match message.payload() {
+            PeerPayload::Replicate { write_sets } => {
+                for write_set in write_sets {
+                    self.install(write_set);
+                }
+
+                Ok(Some(PeerPayload::ReplicateOk {
+                    versions: write_sets
+                        .iter()
+                        .map(|write_set| write_set.version.clone())
+                        .collect(),
+                }))
+            }
+            PeerPayload::ReplicateOk { versions } => {
+                if let Some(acknowledged) = self.acknowledged.get_mut(message.src()) {
+                    acknowledged.extend(versions.iter().cloned());
+                }
+
+                Ok(None)
+            }
+        }
+    }
+}
+
+pub fn main() -> anyhow::Result<()> {
+    Service::new().run::<TxnNode>()?;
+    Ok(())
+}
@@ -27,8 +27,9 @@ impl MaelstromNode for UniqueIdNode {
     type InputPayload = UniqueIdRequest;
     type OutputPayload = UniqueIdResponse;
     type PeerPayload = ();
+    type Config = ();
 
-    fn new(init_message: &Message<InitializationRequest>) -> Self {
+    fn new(init_message: &Message<InitializationRequest>, _: Self::Config) -> Self {
         let InitializationRequest::Init { id, .. } = init_message.payload();
         Self { id: id.clone() }
     }